@@ -1,9 +1,9 @@
-/// This module is responsible for all error-handling related
-/// tasks
+//! This module is responsible for all error-handling related
+//! tasks
 
 pub mod validation;
 
-use std::io::{self, Write};
+use std::io;
 use std::error::Error;
 use std::fmt;
 
@@ -20,6 +20,11 @@ pub enum NogoErrorKind {
     EOFWaitingForUserInput,
     SystemIOError,
     ParsingError,
+    InvalidRuleOption,
+    InvalidBoardMask,
+    InvalidPlayers,
+    InvalidSetup,
+    IllegalMove,
 }
 
 #[derive(Debug)]
@@ -45,7 +50,7 @@ impl<'a> Error for NogoError<'a> {
 impl<'a> NogoError<'a> {
     pub fn new(kind: NogoErrorKind) -> Self {
         let mut error = NogoError {
-            kind: kind,
+            kind,
             status: 0,
             general: "",
             specific: "",
@@ -96,6 +101,31 @@ impl<'a> NogoError<'a> {
                 error.status = 9;
                 error.general = "Error while parsing value";
             }
+
+            NogoErrorKind::InvalidRuleOption => {
+                error.status = 10;
+                error.general = "Invalid rule option";
+            }
+
+            NogoErrorKind::InvalidBoardMask => {
+                error.status = 11;
+                error.general = "Invalid board mask";
+            }
+
+            NogoErrorKind::InvalidPlayers => {
+                error.status = 12;
+                error.general = "Invalid player list";
+            }
+
+            NogoErrorKind::InvalidSetup => {
+                error.status = 13;
+                error.general = "Invalid setup position";
+            }
+
+            NogoErrorKind::IllegalMove => {
+                error.status = 14;
+                error.general = "Illegal move";
+            }
         }
 
         error
@@ -110,11 +140,11 @@ impl<'a> NogoError<'a> {
     }
 
     pub fn general(&self) -> &'a str {
-        &self.general
+        self.general
     }
 
     pub fn specific(&self) -> &'a str {
-        &self.specific
+        self.specific
     }
 
     pub fn set_specific(&mut self, msg: &'a str) {
@@ -141,23 +171,29 @@ impl<'a> ::std::convert::From<::std::num::ParseIntError> for NogoError<'a> {
 pub type Result<'a, T> = ::std::result::Result<T, NogoError<'a>>;
 
 
-/// The API
+// The API
 
 /// normal exit
 pub fn clean_exit() {
+    clean_exit_with_status(0);
+}
+
+/// normal exit, but with a game-specific status (for
+/// instance, when the game ends in a draw)
+pub fn clean_exit_with_status(status: i32) {
     println!("\nThank you for playing nogo!\n");
-    ::std::process::exit(0);
+    ::std::process::exit(status);
 }
 
 /// exit with error code
 pub fn exit_with_error(error: NogoError) {
-    writeln!(io::stderr(), "Error: {}", error.description()).unwrap();
+    eprintln!("Error: {}", error);
     ::std::process::exit(error.status());
 }
 
 
 /// helper method to throw a properly constructed error object
-pub fn construct_error(specific: &str, kind: NogoErrorKind) -> NogoError {
+pub fn construct_error(specific: &str, kind: NogoErrorKind) -> NogoError<'_> {
     let mut err = NogoError::new(kind);
     err.set_specific(specific);
     err
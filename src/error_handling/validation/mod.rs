@@ -1,41 +1,559 @@
-/// This submodule will handle all he validation for game input
-/// as well as the saved file format.
+//! This submodule will handle all he validation for game input
+//! as well as the saved file format.
 
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::time::Duration;
 use error_handling as eh;
 use game_logic as gl;
+use io;
+use game_logic::rules::{Rules, Variant, Scoring, Ko, FullBoard, BothCaptured, Suicide,
+                        TimeControl};
+use game_logic::geometry::Grid;
+use game_logic::topology::Topology;
 
 
 ///
 /// validate the command line arguments for a new
-/// game
+/// game. The players are the two given by their
+/// types, unless a `--players` option lists them.
+/// Besides the rules, the options can give the board
+/// mask, the setup stones and the handicap
 ///
-pub fn validate_new_game_parameters<'a>
-    (p1: &'a str,
-     p2: &'a str,
-     height: &'a str,
-     width: &'a str)
-     -> eh::Result<'a, (gl::PlayerType, gl::PlayerType, i32, i32)> {
-
-    let p1type = match validate_player_type(p1) {
-        Ok(typ) => typ,
-        Err(e) => return Err(e),
+pub fn validate_new_game_parameters<'a>(p1: &'a str,
+                                        p2: &'a str,
+                                        height: &'a str,
+                                        width: &'a str,
+                                        options: &[String])
+                                        -> eh::Result<'a, gl::NewGame> {
+
+    let p1type = validate_player_type(p1)?;
+    let p2type = validate_player_type(p2)?;
+    let (height, width) = validate_board_dimensions(height, width)?;
+
+    let mut players = vec![(gl::PLAYER_ZERO, p1type), (gl::PLAYER_ONE, p2type)];
+    let (mut mask_rows, mut setup_rows, mut handicap) = (None, None, 0);
+    let mut rule_options = Vec::new();
+
+    for option in options.iter() {
+        let mut parts = option.trim_start_matches("--").splitn(2, '=');
+
+        match (parts.next(), parts.next()) {
+            (Some("players"), Some(value)) => players = validate_player_list(value)?,
+            (Some("mask"), Some(rows)) => mask_rows = Some(split_board_rows(rows)),
+            (Some("mask-file"), Some(path)) => mask_rows = Some(io::load_board_rows(path)?),
+            (Some("setup"), Some(rows)) => setup_rows = Some(split_board_rows(rows)),
+            (Some("setup-file"), Some(path)) => setup_rows = Some(io::load_board_rows(path)?),
+            (Some("handicap"), Some(value)) => handicap = validate_handicap(value)?,
+            _ => rule_options.push(option),
+        }
+    }
+
+    let rules = validate_rule_options(&rule_options)?;
+
+    let mut mask = match mask_rows {
+        Some(rows) => validate_board_mask(&rows, height, width)?,
+        None => Vec::new(),
     };
 
-    let p2type = match validate_player_type(p2) {
-        Ok(typ) => typ,
-        Err(e) => return Err(e),
+    let setup = match setup_rows {
+        Some(rows) => {
+            let ids = players.iter().map(|&(id, _)| id).collect::<Vec<_>>();
+            let (stones, blocked) = validate_setup(&rows, &ids, height, width)?;
+            mask.extend(blocked);
+            stones
+        }
+        None => Vec::new(),
     };
 
-    let (height, width) = match validate_board_dimensions(height, width) {
-        Ok((h, w)) => (h, w),
-        Err(e) => return Err(e),
+    let game = gl::NewGame {
+        players,
+        height,
+        width,
+        rules,
+        mask,
+        setup,
+        handicap,
+    };
+
+    validate_new_game(&game)?;
+    Ok(game)
+}
+
+///
+/// validate a new game however it was put together,
+/// on the command line or by another program - the
+/// board dimensions, the rules, the players, the
+/// blocked cells, the setup stones and the handicap
+///
+pub fn validate_new_game<'a>(game: &gl::NewGame) -> eh::Result<'a, ()> {
+    if !within_board_limits(game.height) {
+        return Err(eh::construct_error(HEIGHT_LIMITS, eh::NogoErrorKind::InvalidBoardDimensions));
+    }
+
+    if !within_board_limits(game.width) {
+        return Err(eh::construct_error(WIDTH_LIMITS, eh::NogoErrorKind::InvalidBoardDimensions));
+    }
+
+    validate_rules(&game.rules)?;
+
+    for (i, &(id, kind)) in game.players.iter().enumerate() {
+        validate_player_piece(id, &game.players[..i])?;
+
+        if kind == gl::PlayerType::NONE {
+            return Err(eh::construct_error("each player must be either a human or a computer",
+                                           eh::NogoErrorKind::InvalidPlayers));
+        }
+    }
+
+    validate_player_count(&game.players)?;
+
+    let on_board = |x: i32, y: i32| 0 <= x && x < game.height && 0 <= y && y < game.width;
+
+    if game.mask.iter().any(|&(x, y)| !on_board(x, y)) {
+        return Err(eh::construct_error("mask cells must lie on the board",
+                                       eh::NogoErrorKind::InvalidBoardMask));
+    }
+
+    let blocked = game.mask.iter().cloned().collect::<HashSet<_>>();
+
+    if blocked.len() == (game.height * game.width) as usize {
+        return Err(eh::construct_error("mask must leave at least one cell open",
+                                       eh::NogoErrorKind::InvalidBoardMask));
+    }
+
+    let mut taken = HashSet::new();
+
+    for stone in game.setup.iter() {
+        if !on_board(stone.x(), stone.y()) {
+            return Err(eh::construct_error("setup stones must lie on the board",
+                                           eh::NogoErrorKind::InvalidSetup));
+        }
+
+        if !game.players.iter().any(|&(id, _)| id == *stone.t()) {
+            return Err(eh::construct_error("setup stones must belong to one of the players",
+                                           eh::NogoErrorKind::InvalidSetup));
+        }
+
+        if blocked.contains(&(stone.x(), stone.y())) {
+            return Err(eh::construct_error("setup stones can not be placed on blocked cells \
+                                            of the mask",
+                                           eh::NogoErrorKind::InvalidSetup));
+        }
+
+        if !taken.insert((stone.x(), stone.y())) {
+            return Err(eh::construct_error("setup can not place two stones on the same cell",
+                                           eh::NogoErrorKind::InvalidSetup));
+        }
+    }
+
+    if game.handicap != 0 &&
+       !(gl::handicap::MIN_HANDICAP..=gl::handicap::MAX_HANDICAP).contains(&game.handicap) {
+        return Err(eh::construct_error(HANDICAP_LIMITS, eh::NogoErrorKind::InvalidSetup));
+    }
+
+    Ok(())
+}
+
+/// the rows of a board given inline, separated by `/`
+fn split_board_rows(rows: &str) -> Vec<String> {
+    rows.split('/').map(String::from).collect()
+}
+
+/// check that the rows of a mask or a setup have the
+/// same size as the board
+fn has_board_size(rows: &[String], height: i32, width: i32) -> bool {
+    rows.len() == height as usize && rows.iter().all(|row| row.chars().count() == width as usize)
+}
+
+///
+/// validate the board mask, given either inline as
+/// `--mask=rows` with the rows separated by `/`, or
+/// as `--mask-file=path` with one row per line. Each
+/// row has a `.` for an open cell or a `#` for a
+/// blocked one. The blocked cells are returned
+///
+fn validate_board_mask<'a>(rows: &[String], height: i32, width: i32) -> eh::Result<'a, gl::Mask> {
+    if !has_board_size(rows, height, width) {
+        return Err(eh::construct_error("mask must have a row for each row of the board, and a \
+                                        cell for each column",
+                                       eh::NogoErrorKind::InvalidBoardMask));
+    }
+
+    let mut blocked = Vec::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        for (j, c) in row.chars().enumerate() {
+            match c {
+                gl::BLOCKED => blocked.push((i as i32, j as i32)),
+                gl::EMPTY => {}
+                _ => {
+                    return Err(eh::construct_error("mask cells must be either '.' or '#'",
+                                                   eh::NogoErrorKind::InvalidBoardMask));
+                }
+            }
+        }
+    }
+
+    if blocked.len() == (height * width) as usize {
+        return Err(eh::construct_error("mask must leave at least one cell open",
+                                       eh::NogoErrorKind::InvalidBoardMask));
+    }
+
+    Ok(blocked)
+}
+
+///
+/// validate the setup position, given either inline as
+/// `--setup=rows` with the rows separated by `/`, or as
+/// `--setup-file=path` with one row per line, in the
+/// same form as the board of a save file. The stones
+/// and any blocked cells are returned
+///
+fn validate_setup<'a>(rows: &[String],
+                      ids: &[char],
+                      height: i32,
+                      width: i32)
+                      -> eh::Result<'a, (Vec<gl::Point>, gl::Mask)> {
+    if !has_board_size(rows, height, width) {
+        return Err(eh::construct_error("setup must have a row for each row of the board, and a \
+                                        cell for each column",
+                                       eh::NogoErrorKind::InvalidSetup));
+    }
+
+    let rows = rows.iter().collect::<Vec<_>>();
+    io::parse_player_strings_from_saved_file(&rows, ids)
+}
+
+const HANDICAP_LIMITS: &str = "handicap must be a number between 2 and 9 (inclusive)";
+
+/// validate the number of handicap stones
+fn validate_handicap<'a>(value: &str) -> eh::Result<'a, usize> {
+    match usize::from_str(value) {
+        Ok(n) if (gl::handicap::MIN_HANDICAP..=gl::handicap::MAX_HANDICAP).contains(&n) => Ok(n),
+        _ => {
+            Err(eh::construct_error(HANDICAP_LIMITS, eh::NogoErrorKind::InvalidSetup))
+        }
+    }
+}
+
+///
+/// validate a list of players, given as `id:type`
+/// entries separated by commas in turn order - for
+/// instance `0:h,X:c,Y:c`. Each player needs a piece
+/// of its own, which can not be mistaken for an empty
+/// or blocked cell, and there must be at least two
+///
+pub fn validate_player_list<'a>(list: &str) -> eh::Result<'a, gl::Players> {
+    let mut players: gl::Players = Vec::new();
+
+    for entry in list.split(',') {
+        let mut parts = entry.splitn(2, ':');
+
+        let id = match parts.next().map(|id| id.chars().collect::<Vec<_>>()) {
+            Some(ref id) if id.len() == 1 => id[0],
+            _ => {
+                return Err(eh::construct_error("each player must be given as a single \
+                                                character piece and a type, as in '0:h'",
+                                               eh::NogoErrorKind::InvalidPlayers));
+            }
+        };
+
+        validate_player_piece(id, &players)?;
+
+        let kind = match parts.next() {
+            Some(kind) => validate_player_type(kind)?,
+            None => {
+                return Err(eh::construct_error("each player must be given as a single \
+                                                character piece and a type, as in '0:h'",
+                                               eh::NogoErrorKind::InvalidPlayers));
+            }
+        };
+
+        players.push((id, kind));
+    }
+
+    validate_player_count(&players)?;
+    Ok(players)
+}
+
+/// check that a player piece can not be mistaken for
+/// an empty or blocked cell or for the separators of
+/// a player list, and that no earlier player has it
+fn validate_player_piece<'a>(id: char, earlier: &[(char, gl::PlayerType)]) -> eh::Result<'a, ()> {
+    if id == gl::EMPTY || id == gl::BLOCKED || id.is_whitespace() || id == ',' || id == ':' ||
+       id == '=' {
+        return Err(eh::construct_error("player piece can not be whitespace or one of \
+                                        '.', '#', ',', ':' or '='",
+                                       eh::NogoErrorKind::InvalidPlayers));
+    }
+
+    if earlier.iter().any(|&(other, _)| other == id) {
+        return Err(eh::construct_error("each player must have a different piece",
+                                       eh::NogoErrorKind::InvalidPlayers));
+    }
+
+    Ok(())
+}
+
+/// check that there are enough players for a game
+fn validate_player_count<'a>(players: &[(char, gl::PlayerType)]) -> eh::Result<'a, ()> {
+    if players.len() < 2 {
+        return Err(eh::construct_error("there must be at least two players",
+                                       eh::NogoErrorKind::InvalidPlayers));
+    }
+
+    Ok(())
+}
+
+///
+/// validate the rule options, given either as `--name=value`
+/// on the command line or as `name=value` in the save file
+/// metadata. Rules which are not mentioned keep their
+/// default values
+///
+pub fn validate_rule_options<'a, S: AsRef<str>>(options: &[S]) -> eh::Result<'a, Rules> {
+    let mut rules = Rules::default();
+
+    for option in options.iter() {
+        let option = option.as_ref().trim_start_matches("--");
+        let mut parts = option.splitn(2, '=');
+
+        match (parts.next(), parts.next()) {
+            (Some("variant"), Some(value)) => {
+                rules.variant = match value {
+                    "atari" => Variant::Atari,
+                    "go" => Variant::Go,
+                    "capture" => Variant::Capture,
+                    _ => {
+                        return Err(eh::construct_error("variant must be one of 'atari', 'go' or \
+                                                        'capture'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("full-board"), Some(value)) => {
+                rules.full_board = match value {
+                    "draw" => FullBoard::Draw,
+                    "loss" => FullBoard::PlayerToMoveLoses,
+                    _ => {
+                        return Err(eh::construct_error("full-board must be either 'draw' or 'loss'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("both-captured"), Some(value)) => {
+                rules.both_captured = match value {
+                    "mover-wins" => BothCaptured::MoverWins,
+                    "mover-loses" => BothCaptured::MoverLoses,
+                    _ => {
+                        return Err(eh::construct_error("both-captured must be either 'mover-wins' \
+                                                        or 'mover-loses'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("suicide"), Some(value)) => {
+                rules.suicide = match value {
+                    "allow" => Suicide::Allow,
+                    "forbid" => Suicide::Forbid,
+                    "warn" => Suicide::Warn,
+                    _ => {
+                        return Err(eh::construct_error("suicide must be one of 'allow', 'forbid' \
+                                                        or 'warn'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("scoring"), Some(value)) => {
+                rules.scoring = match value {
+                    "area" => Scoring::Area,
+                    "territory" => Scoring::Territory,
+                    _ => {
+                        return Err(eh::construct_error("scoring must be either 'area' or \
+                                                        'territory'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("komi"), Some(value)) => {
+                rules.komi = match f32::from_str(value) {
+                    Ok(komi) if komi.is_finite() => komi,
+                    _ => {
+                        return Err(eh::construct_error(KOMI_LIMITS,
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("ko"), Some(value)) => {
+                rules.ko = match value {
+                    "none" => Ko::None,
+                    "simple" => Ko::Simple,
+                    "superko" => Ko::Superko,
+                    _ => {
+                        return Err(eh::construct_error("ko must be one of 'none', 'simple' or \
+                                                        'superko'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("capture-target"), Some(value)) => {
+                rules.capture_target = match u32::from_str(value) {
+                    Ok(target) if target > 0 => target,
+                    _ => {
+                        return Err(eh::construct_error(CAPTURE_TARGET_LIMITS,
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("grid"), Some(value)) => {
+                rules.grid = match value {
+                    "square" => Grid::Square,
+                    "hex" => Grid::Hex,
+                    _ => {
+                        return Err(eh::construct_error("grid must be either 'square' or 'hex'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("topology"), Some(value)) => {
+                rules.topology = match value {
+                    "plane" => Topology::Plane,
+                    "cylinder" => Topology::Cylinder,
+                    "torus" => Topology::Torus,
+                    _ => {
+                        return Err(eh::construct_error("topology must be one of 'plane', \
+                                                        'cylinder' or 'torus'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("swap"), Some(value)) => {
+                rules.swap = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        return Err(eh::construct_error("swap must be either 'on' or 'off'",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            (Some("time"), Some(value)) => rules.time = validate_time_control(value)?,
+
+            (Some("hash-seed"), Some(value)) => {
+                rules.hash_seed = match u64::from_str(value) {
+                    Ok(seed) => seed,
+                    _ => {
+                        return Err(eh::construct_error("hash-seed must be a whole number from 0 \
+                                                        to 18446744073709551615",
+                                                       eh::NogoErrorKind::InvalidRuleOption));
+                    }
+                }
+            }
+
+            _ => {
+                return Err(eh::construct_error("unknown rule option",
+                                               eh::NogoErrorKind::InvalidRuleOption));
+            }
+        }
+    }
+
+    Ok(rules)
+}
+
+const KOMI_LIMITS: &str = "komi must be a number";
+const CAPTURE_TARGET_LIMITS: &str = "capture-target must be a positive number";
+const TIME_CONTROLS: &str = "time must be 'none', 'absolute:main', 'fischer:main+increment' or \
+                             'byo-yomi:main+periodsxperiod', in whole seconds";
+
+/// check rules which were put together by hand rather
+/// than read from rule options, against the same limits
+fn validate_rules<'a>(rules: &Rules) -> eh::Result<'a, ()> {
+    if !rules.komi.is_finite() {
+        return Err(eh::construct_error(KOMI_LIMITS, eh::NogoErrorKind::InvalidRuleOption));
+    }
+
+    if rules.capture_target == 0 {
+        return Err(eh::construct_error(CAPTURE_TARGET_LIMITS,
+                                       eh::NogoErrorKind::InvalidRuleOption));
+    }
+
+    if !has_time_to_play(rules.time) {
+        return Err(eh::construct_error(TIME_CONTROLS, eh::NogoErrorKind::InvalidRuleOption));
+    }
+
+    Ok(())
+}
+
+/// check that a time control gives the players time to
+/// play - some main time on an absolute or Fischer
+/// clock, and at least one byo-yomi period of some
+/// length
+fn has_time_to_play(time: TimeControl) -> bool {
+    let none = Duration::from_secs(0);
+
+    match time {
+        TimeControl::None => true,
+        TimeControl::Absolute { main } |
+        TimeControl::Fischer { main, .. } => main > none,
+        TimeControl::ByoYomi { periods, period, .. } => periods > 0 && period > none,
+    }
+}
+
+///
+/// validate the time control, given as `none`,
+/// `absolute:main`, `fischer:main+increment` or
+/// `byo-yomi:main+periodsxperiod`, with every time
+/// in seconds - for instance `byo-yomi:600+5x30`
+///
+fn validate_time_control<'a>(value: &str) -> eh::Result<'a, TimeControl> {
+    let invalid = || eh::construct_error(TIME_CONTROLS, eh::NogoErrorKind::InvalidRuleOption);
+    let seconds = |secs: &str| u64::from_str(secs).map(Duration::from_secs).map_err(|_| invalid());
+
+    let mut parts = value.splitn(2, ':');
+
+    let time = match (parts.next(), parts.next()) {
+        (Some("none"), None) => TimeControl::None,
+
+        (Some("absolute"), Some(main)) => TimeControl::Absolute { main: seconds(main)? },
+
+        (Some("fischer"), Some(times)) => {
+            let mut times = times.splitn(2, '+');
+            let main = seconds(times.next().ok_or_else(invalid)?)?;
+            let increment = seconds(times.next().ok_or_else(invalid)?)?;
+
+            TimeControl::Fischer { main, increment }
+        }
+
+        (Some("byo-yomi"), Some(times)) => {
+            let mut times = times.splitn(2, '+');
+            let main = seconds(times.next().ok_or_else(invalid)?)?;
+
+            let mut overtime = times.next().ok_or_else(invalid)?.splitn(2, 'x');
+            let periods = u32::from_str(overtime.next().ok_or_else(invalid)?).map_err(|_| invalid())?;
+            let period = seconds(overtime.next().ok_or_else(invalid)?)?;
+
+            TimeControl::ByoYomi { main, periods, period }
+        }
+
+        _ => return Err(invalid()),
     };
 
-    Ok((p1type, p2type, height, width))
+    if has_time_to_play(time) { Ok(time) } else { Err(invalid()) }
 }
 
-fn validate_player_type<'a>(pt: &'a str) -> eh::Result<'a, gl::PlayerType> {
+fn validate_player_type<'a>(pt: &str) -> eh::Result<'a, gl::PlayerType> {
     match pt {
         "c" | "C" => Ok(gl::PlayerType::COMPUTER),
         "h" | "H" => Ok(gl::PlayerType::HUMAN),
@@ -47,12 +565,22 @@ fn validate_player_type<'a>(pt: &'a str) -> eh::Result<'a, gl::PlayerType> {
 }
 
 
-fn validate_board_dimensions<'a>(height: &'a str, width: &'a str) -> eh::Result<'a, (i32, i32)> {
+const HEIGHT_LIMITS: &str = "height must be between 4 and 1000 (inclusive)";
+const WIDTH_LIMITS: &str = "width must be between 4 and 1000 (inclusive)";
+
+/// whether a board can have the given height or width
+fn within_board_limits(n: i32) -> bool {
+    (gl::MIN_BOARD_DIMENSION..=gl::MAX_BOARD_DIMENSION).contains(&n)
+}
+
+/// validate the height and width of the board, given
+/// on the command line or in the save file metadata
+pub fn validate_board_dimensions<'a>(height: &str, width: &str) -> eh::Result<'a, (i32, i32)> {
     let (h, w): (i32, i32);
 
     if let Ok(val) = i32::from_str(height) {
-        if val < gl::MIN_BOARD_DIMENSION || val > gl::MAX_BOARD_DIMENSION {
-            return Err(eh::construct_error("height must be between 4 and 1000 (inclusive)",
+        if !within_board_limits(val) {
+            return Err(eh::construct_error(HEIGHT_LIMITS,
                                            eh::NogoErrorKind::InvalidBoardDimensions));
         }
 
@@ -63,8 +591,8 @@ fn validate_board_dimensions<'a>(height: &'a str, width: &'a str) -> eh::Result<
     }
 
     if let Ok(val) = i32::from_str(width) {
-        if val < gl::MIN_BOARD_DIMENSION || val > gl::MAX_BOARD_DIMENSION {
-            return Err(eh::construct_error("width must be between 4 and 1000 (inclusive)",
+        if !within_board_limits(val) {
+            return Err(eh::construct_error(WIDTH_LIMITS,
                                            eh::NogoErrorKind::InvalidBoardDimensions));
         }
 
@@ -78,14 +606,7 @@ fn validate_board_dimensions<'a>(height: &'a str, width: &'a str) -> eh::Result<
 }
 
 /// Validate the player move against the existing
-/// state of the board
-pub fn validate_user_move(board: &gl::NogoBoard, mv: (i32, i32)) -> bool {
-    let points = board.liberties();
-
-    for point in points.iter() {
-        if (point.x(), point.y()) == mv {
-            return true;
-        }
-    }
-    false
+/// state of the board and the rules of the game
+pub fn validate_user_move(board: &gl::NogoBoard, mv: (i32, i32), player: char) -> bool {
+    board.is_legal(mv.0, mv.1, player)
 }
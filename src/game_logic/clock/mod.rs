@@ -0,0 +1,154 @@
+//! This submodule keeps the time each player has left.
+//!
+//! Every player of a timed game has a `Clock`, set by the
+//! time control of the game. The time a turn takes is run
+//! off the clock of the player who took it, and the clock is
+//! then made up for the next turn - by the increment under
+//! Fischer timing, or back to a full period once a player is
+//! into byo-yomi. A player whose clock runs out loses.
+//!
+//! In byo-yomi, `remaining` is the time left in the current
+//! period and `periods` the number of periods after it, so a
+//! clock has run out exactly when nothing remains on it.
+
+use std::fmt;
+use std::time::Duration;
+use super::rules::TimeControl;
+
+
+/// the time a player has left
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Clock {
+    time: TimeControl,
+    remaining: Duration,
+    periods: u32,
+}
+
+impl Clock {
+    /// a full clock for the time control
+    pub fn new(time: TimeControl) -> Self {
+        match time {
+            TimeControl::None => Clock::restore(time, Duration::from_secs(0), 0),
+            TimeControl::Absolute { main } |
+            TimeControl::Fischer { main, .. } => Clock::restore(time, main, 0),
+            TimeControl::ByoYomi { main, periods, period } => {
+                // with no main time the first period starts
+                // straight away (a time control without any
+                // periods gives a clock which has run out)
+                if main == Duration::from_secs(0) {
+                    Clock::restore(time, period, periods.saturating_sub(1))
+                } else {
+                    Clock::restore(time, main, periods)
+                }
+            }
+        }
+    }
+
+    /// a clock with the given time left, as read from
+    /// a save file
+    pub fn restore(time: TimeControl, remaining: Duration, periods: u32) -> Self {
+        Clock {
+            time,
+            remaining,
+            periods,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.time
+    }
+
+    /// the time left on the clock - of the main time, or
+    /// of the current period in byo-yomi
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// the byo-yomi periods left after the current one
+    pub fn periods(&self) -> u32 {
+        self.periods
+    }
+
+    /// check whether the clock runs at all
+    pub fn is_timed(&self) -> bool {
+        self.time != TimeControl::None
+    }
+
+    /// check whether the clock has run out
+    pub fn is_out_of_time(&self) -> bool {
+        self.is_timed() && self.remaining == Duration::from_secs(0)
+    }
+
+    /// check whether the main time is used up, and the
+    /// player is into byo-yomi
+    pub fn in_byo_yomi(&self) -> bool {
+        match self.time {
+            TimeControl::ByoYomi { periods, .. } => self.periods < periods,
+            _ => false,
+        }
+    }
+
+    /// take the time spent so far in a turn off the clock,
+    /// moving on to the next byo-yomi period each time one
+    /// runs out. Return whether any time is left
+    pub fn run(&mut self, elapsed: Duration) -> bool {
+        if !self.is_timed() {
+            return true;
+        }
+
+        let mut elapsed = elapsed;
+
+        while elapsed >= self.remaining {
+            elapsed -= self.remaining;
+
+            match self.time {
+                TimeControl::ByoYomi { period, .. } if self.periods > 0 => {
+                    self.periods -= 1;
+                    self.remaining = period;
+                }
+                _ => {
+                    self.remaining = Duration::from_secs(0);
+                    return false;
+                }
+            }
+        }
+
+        self.remaining -= elapsed;
+        true
+    }
+
+    /// make the clock up at the end of a turn played in
+    /// time
+    pub fn end_turn(&mut self) {
+        if self.is_out_of_time() {
+            return;
+        }
+
+        match self.time {
+            TimeControl::Fischer { increment, .. } => self.remaining += increment,
+            TimeControl::ByoYomi { period, .. } if self.in_byo_yomi() => self.remaining = period,
+            _ => {}
+        }
+    }
+}
+
+/// show the time left as minutes and seconds, rounded up
+/// so that a clock only shows `0:00` once it has run out,
+/// along with the byo-yomi periods - for instance
+/// `4:55 + 5x30s` before byo-yomi and `0:21, 3 periods left`
+/// once in it, counting the current period
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = (self.remaining.as_millis() as u64).div_ceil(1000);
+        write!(f, "{}:{:02}", secs / 60, secs % 60)?;
+
+        match self.time {
+            TimeControl::ByoYomi { period, .. } if !self.in_byo_yomi() => {
+                write!(f, " + {}x{}s", self.periods, period.as_secs())
+            }
+            TimeControl::ByoYomi { .. } if self.periods == 0 => write!(f, ", last period"),
+            TimeControl::ByoYomi { .. } => write!(f, ", {} periods left", self.periods + 1),
+            _ => Ok(()),
+        }
+    }
+}
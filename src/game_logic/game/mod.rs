@@ -0,0 +1,386 @@
+//! This submodule holds a whole game of nogo, for running
+//! games from other programs.
+//!
+//! A `Game` is a board together with the player to move and
+//! the outcome once the game is over. Moves are handed to it
+//! one at a time, and it reports what each of them led to.
+//! It never reads input, writes output or ends the process -
+//! that is left to whoever drives the game, such as the
+//! `nogo` binary. Observers can be added to a game to follow
+//! it as it is played.
+//!
+//! In a timed game the clock of the player to move runs from
+//! the start of the game, and the time each turn takes is run
+//! off it when the move is played. A player who has run out
+//! of time by then resigns instead.
+
+use std::time::Instant;
+use error_handling as eh;
+use io;
+use super::{NogoBoard, NewGame, Move, Outcome, Point};
+use super::clock::Clock;
+use super::groups::Group;
+use super::observer::GameObserver;
+use super::rules::TimeControl;
+use super::win_conditions::{self, WinCondition};
+
+
+/// a game in progress. `current` is the player to move,
+/// `outcome` is set once the game is over, and
+/// `observers` are told about everything that happens.
+/// `clocks` are the clocks of the players in turn order
+/// in a timed game, and `turn_started` is when the clock
+/// of the player to move was started, while it runs
+#[derive(Debug)]
+pub struct Game {
+    board: NogoBoard,
+    current: char,
+    outcome: Option<Outcome>,
+    observers: Vec<Box<dyn GameObserver>>,
+    clocks: Vec<Clock>,
+    turn_started: Option<Instant>,
+}
+
+/// what a move led to - the stones it captured, whether
+/// taken off the board or (in an atari game) left on it,
+/// the strings it left in atari, the players it knocked
+/// out, and the outcome of the game if the move ended it.
+/// `out_of_time` is the player who ran out of time before
+/// the move, and so resigned instead
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveOutcome {
+    pub captured: Vec<Point>,
+    pub atari: Vec<Group>,
+    pub eliminated: Vec<char>,
+    pub outcome: Option<Outcome>,
+    pub out_of_time: Option<char>,
+}
+
+impl Game {
+    /// start a fresh game, with the setup and handicap
+    /// stones (if any) already on the board
+    pub fn new<'a>(game: NewGame) -> eh::Result<'a, Self> {
+        let win_condition = win_conditions::for_rules(&game.rules);
+        Game::with_win_condition(game, win_condition)
+    }
+
+    /// start a fresh game as `new` does, but ended by the
+    /// given condition rather than the one which goes with
+    /// its rules
+    pub fn with_win_condition<'a>(game: NewGame,
+                                  win_condition: Box<dyn WinCondition>)
+                                  -> eh::Result<'a, Self> {
+        eh::validation::validate_new_game(&game)?;
+
+        let mut board = NogoBoard::with_win_condition(&game.players,
+                                                      game.height,
+                                                      game.width,
+                                                      game.rules,
+                                                      &game.mask,
+                                                      win_condition);
+        let current = super::set_up_board(&mut board, game.setup, game.handicap)?;
+        let clocks = new_clocks(&board);
+
+        Ok(Game {
+            board,
+            current,
+            outcome: None,
+            observers: Vec::new(),
+            clocks,
+            turn_started: None,
+        })
+    }
+
+    /// continue a game from the contents of a save file,
+    /// with the clocks as they were saved. A game saved
+    /// once it was over is restored with its outcome
+    pub fn restore<'a>(game_data: &[String]) -> eh::Result<'a, Self> {
+        let (board, current, mut clocks) = super::restore_game(game_data)?;
+        if clocks.is_empty() {
+            clocks = new_clocks(&board);
+        }
+
+        let outcome = super::game_outcome(&board);
+
+        Ok(Game {
+            board,
+            current,
+            outcome,
+            observers: Vec::new(),
+            clocks,
+            turn_started: None,
+        })
+    }
+
+    /// add an observer to be told about the game from
+    /// now on
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// let the observers know that the game is starting,
+    /// and start the clock of the player to move - to be
+    /// called once, before the first move
+    pub fn start(&mut self) {
+        self.turn_started = Some(Instant::now());
+        self.notify(|observer, game| observer.game_started(game));
+    }
+
+    /// play a move for the player to move. Any players
+    /// knocked out by the move drop out of the game, and
+    /// the turn passes on, unless the game is over
+    pub fn play<'a>(&mut self, mov: Move) -> eh::Result<'a, MoveOutcome> {
+        self.check_move(mov)?;
+
+        let in_time = self.run_clock();
+        let (mov, out_of_time) = if in_time {
+            if let Some(clock) = self.clock_mut(self.current) {
+                clock.end_turn();
+            }
+            (mov, None)
+        } else {
+            (Move::Resign(self.current), Some(self.current))
+        };
+
+        match mov {
+            Move::Stone(p) => self.board.play(p),
+            Move::Pass(id) => self.board.pass(id),
+            Move::Resign(id) => self.board.resign(id),
+            Move::Swap(id) => self.board.swap(id),
+        }
+
+        let outcome = self.board.win_condition.outcome(&self.board);
+
+        // in an atari game captured strings stay on the
+        // board, so they are found by their liberties
+        let mut captured = self.strings_at(mov, |group| group.liberties().is_empty())
+            .iter()
+            .flat_map(|group| group.stones().to_vec())
+            .collect::<Vec<_>>();
+
+        let eliminated = match outcome {
+            Some(_) => Vec::new(),
+            None => self.board.win_condition.eliminated(&self.board),
+        };
+
+        for &id in eliminated.iter() {
+            self.board.eliminate(id);
+        }
+
+        self.outcome = outcome;
+        self.current = self.board.player_after(mov);
+
+        if outcome.is_some() {
+            self.turn_started = None;
+        }
+
+        for stone in self.board.removed_by_last_turn() {
+            if !captured.contains(&stone) {
+                captured.push(stone);
+            }
+        }
+
+        let result = MoveOutcome {
+            captured,
+            atari: self.strings_at(mov, Group::is_in_atari),
+            eliminated,
+            outcome,
+            out_of_time,
+        };
+
+        self.notify(|observer, game| {
+            observer.move_played(game, mov);
+
+            if !result.captured.is_empty() {
+                observer.captured(game, &result.captured);
+            }
+
+            for group in result.atari.iter() {
+                observer.put_in_atari(game, group);
+            }
+
+            if let Some(outcome) = result.outcome {
+                observer.game_over(game, outcome);
+            }
+        });
+
+        Ok(result)
+    }
+
+    /// the strings on or next to the stone just played
+    /// which `wanted` picks out
+    fn strings_at<F>(&self, mov: Move, wanted: F) -> Vec<Group>
+        where F: Fn(&Group) -> bool
+    {
+        let p = match mov {
+            Move::Stone(p) => p,
+            _ => return Vec::new(),
+        };
+
+        let mut touched = self.board.state.neighbours(p.x, p.y);
+        touched.push((p.x, p.y));
+
+        let mut strings: Vec<Group> = Vec::new();
+        for (x, y) in touched {
+            if let Some(group) = self.board.group_at(x, y) {
+                if wanted(&group) && strings.iter().all(|other| other.id() != group.id()) {
+                    strings.push(group);
+                }
+            }
+        }
+
+        strings
+    }
+
+    /// take the time since the clock was started off the
+    /// clock of the player to move, and start it again.
+    /// Return whether the player still has time left
+    fn run_clock(&mut self) -> bool {
+        let elapsed = match self.turn_started {
+            Some(started) => started.elapsed(),
+            None => return true,
+        };
+
+        self.turn_started = Some(Instant::now());
+
+        match self.clock_mut(self.current) {
+            Some(clock) => clock.run(elapsed),
+            None => true,
+        }
+    }
+
+    fn clock_mut(&mut self, id: char) -> Option<&mut Clock> {
+        let i = self.board.player_ids().iter().position(|&other| other == id)?;
+        self.clocks.get_mut(i)
+    }
+
+    /// tell every observer about something that has
+    /// happened in the game
+    fn notify<F>(&mut self, mut tell: F)
+        where F: FnMut(&mut dyn GameObserver, &Game)
+    {
+        let mut observers = ::std::mem::take(&mut self.observers);
+
+        for observer in observers.iter_mut() {
+            tell(observer.as_mut(), self);
+        }
+
+        self.observers = observers;
+    }
+
+    /// check that the move may be played now
+    fn check_move<'a>(&self, mov: Move) -> eh::Result<'a, ()> {
+        let illegal = |msg| Err(eh::construct_error(msg, eh::NogoErrorKind::IllegalMove));
+
+        if self.outcome.is_some() {
+            return illegal("the game is over");
+        }
+
+        if mov.player() != self.current {
+            return illegal("not the player's turn");
+        }
+
+        match mov {
+            Move::Stone(p) if !self.board.is_legal(p.x, p.y, p.t) => {
+                illegal("the stone cannot be played there")
+            }
+            Move::Swap(id) if !self.board.can_swap(id) => illegal("the swap rule does not apply"),
+            _ => Ok(()),
+        }
+    }
+
+    /// the move the computer would play for the player to
+    /// move
+    pub fn computer_move(&self) -> Move {
+        super::computer_move(&self.board, self.current)
+    }
+
+    /// take back the last turn of the game, and return it.
+    /// Clocks are not turned back - the time spent so far
+    /// is run off the clock of the player to move
+    pub fn undo(&mut self) -> Option<Move> {
+        let mov = self.board.undo()?;
+        self.run_clock();
+
+        self.current = mov.player();
+        self.outcome = None;
+
+        Some(mov)
+    }
+
+    /// make the last turn taken back again, and return it
+    pub fn redo(&mut self) -> Option<Move> {
+        let mov = self.board.redo()?;
+        self.run_clock();
+
+        self.current = self.board.player_after(mov);
+        self.outcome = self.board.win_condition.outcome(&self.board);
+
+        if self.outcome.is_some() {
+            self.turn_started = None;
+        }
+
+        Some(mov)
+    }
+
+    /// the player to move
+    pub fn current_player(&self) -> char {
+        self.current
+    }
+
+    pub fn board(&self) -> &NogoBoard {
+        &self.board
+    }
+
+    /// the clock of player `id` in a timed game, with the
+    /// time of the turn in progress taken off it
+    pub fn clock(&self, id: char) -> Option<Clock> {
+        let i = self.board.player_ids().iter().position(|&other| other == id)?;
+        let mut clock = *self.clocks.get(i)?;
+
+        if let (true, Some(started)) = (id == self.current, self.turn_started) {
+            clock.run(started.elapsed());
+        }
+
+        Some(clock)
+    }
+
+    /// the outcome of the game, once it is over
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// a summary of the game to show along with its
+    /// outcome (for instance, the score), if any
+    pub fn summary(&self) -> Option<String> {
+        self.board.win_condition.summary(&self.board)
+    }
+
+    /// the game in save file form, one line per entry
+    pub fn save_data(&self) -> Vec<String> {
+        let clocks = self.board
+            .player_ids()
+            .into_iter()
+            .filter_map(|id| self.clock(id))
+            .collect::<Vec<_>>();
+
+        super::get_current_game_state(&self.board, self.current, &clocks)
+    }
+
+    /// save the game to the file at `path`
+    pub fn save<'a>(&mut self, path: &str) -> eh::Result<'a, ()> {
+        io::save_game_state(path, self.save_data())?;
+        self.notify(|observer, game| observer.saved(game, path));
+
+        Ok(())
+    }
+}
+
+/// full clocks for every player of the game, if it is
+/// timed
+fn new_clocks(board: &NogoBoard) -> Vec<Clock> {
+    match board.rules().time {
+        TimeControl::None => Vec::new(),
+        time => vec![Clock::new(time); board.player_ids().len()],
+    }
+}
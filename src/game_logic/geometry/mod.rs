@@ -0,0 +1,106 @@
+//! This submodule holds the geometry of the board - the
+//! shape of its cells, and which cells are adjacent.
+//!
+//! A square grid cell has four neighbours. A hex grid is laid
+//! out as a parallelogram of rows, each shifted half a cell to
+//! the right of the one above, so a cell also touches the cell
+//! up and to the right and the cell down and to the left, for
+//! six neighbours in all. Either grid can be joined across its
+//! edges by the topology of the board.
+
+use super::symmetry::{Transform, TRANSFORMS};
+use super::topology::Topology;
+
+
+/// the shape of the cells of the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Grid {
+    Square,
+    Hex,
+}
+
+impl Grid {
+    /// the offsets from a cell to each of its neighbours
+    pub fn offsets(&self) -> &'static [(i32, i32)] {
+        match *self {
+            Grid::Square => &[(0, -1), (0, 1), (-1, 0), (1, 0)],
+            Grid::Hex => &[(0, -1), (0, 1), (-1, 0), (1, 0), (-1, 1), (1, -1)],
+        }
+    }
+}
+
+/// the size, grid and topology of the board, which
+/// together decide how cells are numbered and which
+/// cells are adjacent
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Geometry {
+    limits: (i32, i32),
+    grid: Grid,
+    topology: Topology,
+}
+
+impl Geometry {
+    pub fn new(limits: (i32, i32), grid: Grid, topology: Topology) -> Self {
+        Geometry {
+            limits,
+            grid,
+            topology,
+        }
+    }
+
+    pub fn limits(&self) -> (i32, i32) {
+        self.limits
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// the number of cells of the board
+    pub fn area(&self) -> usize {
+        (self.limits.0 * self.limits.1) as usize
+    }
+
+    /// map board coordinates to a cell index, if the
+    /// coordinates lie on the board
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.limits.0 || y >= self.limits.1 {
+            None
+        } else {
+            Some((x * self.limits.1 + y) as usize)
+        }
+    }
+
+    /// map a cell index back to board coordinates
+    pub fn coords(&self, cell: usize) -> (i32, i32) {
+        let cell = cell as i32;
+        (cell / self.limits.1, cell % self.limits.1)
+    }
+
+    /// the cells adjacent to the given cell, as joined
+    /// by the topology of the board
+    pub fn neighbours(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        self.grid
+            .offsets()
+            .iter()
+            .filter_map(|&(dx, dy)| self.topology.wrap(x + dx, y + dy, self.limits))
+            .collect()
+    }
+    /// the transforms which map the board onto itself, so
+    /// that adjacent cells stay adjacent. The identity is
+    /// always the first
+    pub fn symmetries(&self) -> Vec<Transform> {
+        let offsets = self.grid.offsets();
+        let square = self.limits.0 == self.limits.1;
+
+        TRANSFORMS.iter()
+            .cloned()
+            .filter(|t| !t.swaps_axes() || (square && self.topology != Topology::Cylinder))
+            .filter(|t| offsets.iter().all(|&offset| offsets.contains(&t.apply_offset(offset))))
+            .collect()
+    }
+}
@@ -0,0 +1,238 @@
+//! This submodule keeps track of the groups ("strings") of
+//! stones on the board.
+//!
+//! Every occupied cell belongs to exactly one group of
+//! horizontally or vertically connected stones of the same
+//! colour. Groups are held in a union-find structure over the
+//! cell indices of the board, so placing a stone that touches
+//! several existing strings merges all of them into one.
+//!
+//! Each group also owns its set of liberties, which is kept up
+//! to date as stones are placed so that checking for a capture
+//! only ever needs to look at the groups next to the last move.
+//!
+//! The tracker itself is internal to the board, which hands out
+//! a `Group` for any string a caller asks about.
+
+use std::collections::{HashMap, HashSet};
+use super::Point;
+
+/// marker for a cell which does not hold a stone
+const NO_GROUP: usize = usize::MAX;
+
+/// union-find over the cells of the board. Each group also
+/// keeps its members in a circular list (`next`) so that
+/// membership can be enumerated without scanning the board.
+/// `liberties` is keyed by the representative of each group
+#[derive(Debug, Clone)]
+pub struct GroupTracker {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    next: Vec<usize>,
+    liberties: HashMap<usize, HashSet<usize>>,
+}
+
+impl GroupTracker {
+    pub fn new(cells: usize) -> Self {
+        GroupTracker {
+            parent: vec![NO_GROUP; cells],
+            size: vec![0; cells],
+            next: vec![NO_GROUP; cells],
+            liberties: HashMap::new(),
+        }
+    }
+
+    /// check whether the cell is part of any group
+    pub fn contains(&self, cell: usize) -> bool {
+        self.parent[cell] != NO_GROUP
+    }
+
+    /// retrieve the representative (group identity) of
+    /// the group the cell belongs to, if any. Union by size
+    /// keeps the trees shallow, so no path compression is
+    /// needed and lookups do not require mutable access
+    pub fn find(&self, cell: usize) -> Option<usize> {
+        if !self.contains(cell) {
+            return None;
+        }
+
+        let mut root = cell;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        Some(root)
+    }
+
+    /// start a new group consisting of the single cell, with
+    /// the given empty cells as its liberties
+    pub fn add(&mut self, cell: usize, liberties: HashSet<usize>) {
+        self.parent[cell] = cell;
+        self.size[cell] = 1;
+        self.next[cell] = cell;
+        self.liberties.insert(cell, liberties);
+    }
+
+    /// the given cell has just been filled, so it is no
+    /// longer a liberty of the group of `neighbour`
+    pub fn remove_liberty(&mut self, neighbour: usize, cell: usize) {
+        if let Some(root) = self.find(neighbour) {
+            if let Some(libs) = self.liberties.get_mut(&root) {
+                libs.remove(&cell);
+            }
+        }
+    }
+
+    /// the stone on the given cell has been taken off the
+    /// board, so the cell is a liberty of the group of
+    /// `neighbour` again
+    pub fn add_liberty(&mut self, neighbour: usize, cell: usize) {
+        if let Some(root) = self.find(neighbour) {
+            if let Some(libs) = self.liberties.get_mut(&root) {
+                libs.insert(cell);
+            }
+        }
+    }
+
+    /// take the whole group of the given cell off the
+    /// board, and return the cells it occupied
+    pub fn remove(&mut self, cell: usize) -> Vec<usize> {
+        let members = self.members(cell);
+
+        if let Some(root) = self.find(cell) {
+            self.liberties.remove(&root);
+        }
+
+        for &member in members.iter() {
+            self.parent[member] = NO_GROUP;
+            self.size[member] = 0;
+            self.next[member] = NO_GROUP;
+        }
+
+        members
+    }
+
+    /// the liberties of the group of the given cell
+    pub fn liberties(&self, cell: usize) -> Option<&HashSet<usize>> {
+        self.find(cell).and_then(|root| self.liberties.get(&root))
+    }
+
+    /// the number of liberties of the group of the given
+    /// cell (zero if the cell holds no stone)
+    pub fn liberty_count(&self, cell: usize) -> usize {
+        self.liberties(cell).map_or(0, |libs| libs.len())
+    }
+
+    /// merge the groups of the two given cells, and return
+    /// the identity of the resulting group
+    pub fn union(&mut self, a: usize, b: usize) -> usize {
+        let (ra, rb) = match (self.find(a), self.find(b)) {
+            (Some(ra), Some(rb)) => (ra, rb),
+            _ => panic!("cannot merge a cell that is not part of any group"),
+        };
+
+        if ra == rb {
+            return ra;
+        }
+
+        let (root, child) = if self.size[ra] >= self.size[rb] { (ra, rb) } else { (rb, ra) };
+
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+
+        // splice the two circular member lists together
+        self.next.swap(root, child);
+
+        // and fold the smaller liberty set into the larger one
+        let mut child_libs = self.liberties.remove(&child).unwrap_or_default();
+        let mut root_libs = self.liberties.remove(&root).unwrap_or_default();
+        if child_libs.len() > root_libs.len() {
+            ::std::mem::swap(&mut child_libs, &mut root_libs);
+        }
+        root_libs.extend(child_libs);
+        self.liberties.insert(root, root_libs);
+
+        root
+    }
+
+    /// the number of stones in the group of the given cell
+    pub fn size(&self, cell: usize) -> usize {
+        self.find(cell).map_or(0, |root| self.size[root])
+    }
+
+    /// all the cells that belong to the same group as the
+    /// given cell (including the cell itself)
+    pub fn members(&self, cell: usize) -> Vec<usize> {
+        let mut members = Vec::new();
+
+        if !self.contains(cell) {
+            return members;
+        }
+
+        let mut curr = cell;
+        loop {
+            members.push(curr);
+            curr = self.next[curr];
+
+            if curr == cell {
+                break;
+            }
+        }
+
+        members
+    }
+
+    /// the identities of all the groups currently on the
+    /// board
+    pub fn roots(&self) -> Vec<usize> {
+        self.liberties.keys().cloned().collect()
+    }
+}
+
+
+/// a string of stones as seen from outside the board -
+/// its identity, the player it belongs to, its stones
+/// and its liberties
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    id: usize,
+    owner: char,
+    stones: Vec<Point>,
+    liberties: HashSet<Point>,
+}
+
+impl Group {
+    pub fn new(id: usize, owner: char, stones: Vec<Point>, liberties: HashSet<Point>) -> Self {
+        Group {
+            id,
+            owner,
+            stones,
+            liberties,
+        }
+    }
+
+    /// the identity of the group, the same for every
+    /// stone of the string - the cell of its first stone,
+    /// row by row
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn owner(&self) -> char {
+        self.owner
+    }
+
+    pub fn stones(&self) -> &[Point] {
+        &self.stones
+    }
+
+    pub fn liberties(&self) -> &HashSet<Point> {
+        &self.liberties
+    }
+
+    /// check whether the string is down to its last
+    /// liberty, and so could be captured by the next move
+    pub fn is_in_atari(&self) -> bool {
+        self.liberties.len() == 1
+    }
+}
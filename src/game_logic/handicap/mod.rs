@@ -0,0 +1,54 @@
+//! This submodule places the handicap stones of a new game.
+//!
+//! The stones go on the usual star points - the four corner
+//! points first, then the points halfway along the sides, with
+//! the centre point taken for an odd number of stones from five
+//! up. The corner points sit on the third line of a small board
+//! and the fourth line of a board of thirteen or more, and on
+//! the second line of a board too small for either.
+
+/// the fewest and the most handicap stones a game
+/// can start with
+pub const MIN_HANDICAP: usize = 2;
+pub const MAX_HANDICAP: usize = 9;
+
+/// the points for `n` handicap stones on a board of the
+/// given size. On a small board some of the points fall
+/// together, in which case fewer than `n` are returned
+pub fn handicap_points(height: i32, width: i32, n: usize) -> Vec<(i32, i32)> {
+    let line = match height.min(width) {
+        d if d >= 13 => 3,
+        d if d >= 7 => 2,
+        _ => 1,
+    };
+
+    let (top, bottom, left, right) = (line, height - 1 - line, line, width - 1 - line);
+    let (middle, centre) = (height / 2, width / 2);
+
+    let corners = [(top, right), (bottom, left), (bottom, right), (top, left)];
+    let sides = [(middle, left), (middle, right), (top, centre), (bottom, centre)];
+
+    let candidates = match n {
+        n if n <= corners.len() => corners[..n].to_vec(),
+        n if n % 2 == 1 => {
+            let mut points = corners.to_vec();
+            points.extend_from_slice(&sides[..n - 5]);
+            points.push((middle, centre));
+            points
+        }
+        n => {
+            let mut points = corners.to_vec();
+            points.extend_from_slice(&sides[..n - 4]);
+            points
+        }
+    };
+
+    let mut points = Vec::new();
+    for point in candidates {
+        if !points.contains(&point) {
+            points.push(point);
+        }
+    }
+
+    points
+}
@@ -0,0 +1,73 @@
+//! This submodule keeps the history of the positions a game
+//! has gone through, for detecting repeated positions under
+//! the ko rules.
+//!
+//! Positions are identified by the Zobrist hash of the board,
+//! so two positions are considered the same when every cell
+//! holds the same piece, whoever is to move.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct PositionHistory {
+    positions: Vec<u64>,
+    seen: HashMap<u64, usize>,
+}
+
+impl PositionHistory {
+    /// start a history at the given position
+    pub fn new(initial: u64) -> Self {
+        let mut history = PositionHistory {
+            positions: Vec::new(),
+            seen: HashMap::new(),
+        };
+
+        history.push(initial);
+        history
+    }
+
+    /// record the position reached after a move (or a pass)
+    pub fn push(&mut self, position: u64) {
+        self.positions.push(position);
+        *self.seen.entry(position).or_insert(0) += 1;
+    }
+
+    /// the number of positions recorded so far
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// check whether no position has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// forget the positions recorded after the first
+    /// `len`, when moves are taken back
+    pub fn truncate(&mut self, len: usize) {
+        while self.positions.len() > len {
+            if let Some(position) = self.positions.pop() {
+                if let Some(count) = self.seen.get_mut(&position) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.seen.remove(&position);
+                    }
+                }
+            }
+        }
+    }
+
+    /// simple ko - check whether the position is the one
+    /// from just before the last move, that is, whether a
+    /// move would undo the opponent's last move
+    pub fn is_ko(&self, position: u64) -> bool {
+        let len = self.positions.len();
+        len >= 2 && self.positions[len - 2] == position
+    }
+
+    /// positional superko - check whether the position has
+    /// occurred at any point of the game so far
+    pub fn is_repeat(&self, position: u64) -> bool {
+        self.seen.contains_key(&position)
+    }
+}
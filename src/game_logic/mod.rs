@@ -1,21 +1,52 @@
-/// This module contains all the game-related logic
+//! This module contains all the game-related logic
+
+pub mod clock;
+pub mod game;
+pub mod geometry;
+pub mod groups;
+pub mod handicap;
+pub mod history;
+pub mod observer;
+pub mod rules;
+pub mod scoring;
+pub mod symmetry;
+pub mod topology;
+pub mod win_conditions;
+pub mod zobrist;
 
 use std::collections::HashSet;
 use error_handling as eh;
 use io;
+use self::clock::Clock;
+use self::groups::{Group, GroupTracker};
+use self::history::PositionHistory;
+use self::rules::{Rules, Variant, Ko, BothCaptured, Suicide};
+use self::geometry::Geometry;
+use self::symmetry::{CanonicalPosition, Transform};
+use self::win_conditions::WinCondition;
+use self::zobrist::Zobrist;
 
 
-/// Some game constants
+// Some game constants
 
 pub const MIN_BOARD_DIMENSION: i32 = 4;
 pub const MAX_BOARD_DIMENSION: i32 = 1000;
 
 pub const PLAYER_ZERO: char = '0';
 pub const PLAYER_ONE: char = 'X';
+pub const EMPTY: char = '.';
+pub const BLOCKED: char = '#';
 
+/// the blocked cells of a board
+pub type Mask = Vec<(i32, i32)>;
 
-/// Constants for computer-generated
-/// moves
+/// the pieces and types of the players of a game, in
+/// turn order
+pub type Players = Vec<(char, PlayerType)>;
+
+
+// Constants for computer-generated
+// moves
 
 const IR0: i32 = 1;
 const IRX: i32 = 2;
@@ -28,6 +59,10 @@ const FX: i32 = 17;
 
 const MOD_FACTOR: i32 = 10000003;
 
+/// exit status of a drawn game, kept clear of the
+/// error statuses
+pub const DRAW_STATUS: i32 = 20;
+
 /// Game related data structures
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PlayerType {
@@ -38,39 +73,133 @@ pub enum PlayerType {
 
 pub enum PlayerInput {
     Point(i32, i32),
+    Pass,
+    Swap,
+    Resign,
+    Undo,
+    Redo,
     Save(String),
 }
 
-/// the overall board -it holds state, but does
-/// not really do any processng on its own
+pub use self::game::{Game, MoveOutcome};
+pub use self::observer::GameObserver;
+
+/// a turn of the game, as kept in the move history -
+/// a stone played, or a player passing, resigning or
+/// taking over the first stone under the swap rule
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Move {
+    Stone(Point),
+    Pass(char),
+    Resign(char),
+    Swap(char),
+}
+
+impl Move {
+    /// the player who made the turn
+    pub fn player(&self) -> char {
+        match *self {
+            Move::Stone(p) => p.t,
+            Move::Pass(id) | Move::Resign(id) | Move::Swap(id) => id,
+        }
+    }
+}
+
+/// everything needed to start a fresh game - the
+/// players in turn order, the board and its rules,
+/// the blocked cells, the stones set up on the board
+/// before the first move, and the number of handicap
+/// stones for the first player
 #[derive(Debug)]
+pub struct NewGame {
+    pub players: Players,
+    pub height: i32,
+    pub width: i32,
+    pub rules: Rules,
+    pub mask: Mask,
+    pub setup: Vec<Point>,
+    pub handicap: usize,
+}
+
+/// how a finished game ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Win(char),
+    Draw,
+}
+
+impl Outcome {
+    /// the exit status the game ends with
+    pub fn status(&self) -> i32 {
+        match *self {
+            Outcome::Win(_) => 0,
+            Outcome::Draw => DRAW_STATUS,
+        }
+    }
+}
+
+/// the overall board -it holds state, but does
+/// not really do any processng on its own.
+/// `history` holds every position the game has
+/// gone through, for the ko rules, and
+/// `win_condition` decides when the game is over.
+/// `turns` is the move history, with what is needed
+/// to take each turn back, and `undone` the turns
+/// taken back which can be made again, the most
+/// recently taken back last
+#[derive(Debug, Clone)]
 pub struct NogoBoard {
     height: i32,
     width: i32,
+    rules: Rules,
     state: NogoBoardState,
+    history: PositionHistory,
+    win_condition: Box<dyn WinCondition>,
+    turns: Vec<Turn>,
+    undone: Vec<(Move, Vec<char>)>,
 }
 
 impl NogoBoard {
-    fn new(p1: PlayerType, p2: PlayerType, h: i32, w: i32) -> Self {
+    fn new(players: &[(char, PlayerType)],
+           h: i32,
+           w: i32,
+           rules: Rules,
+           mask: &[(i32, i32)])
+           -> Self {
+        let win_condition = win_conditions::for_rules(&rules);
+        NogoBoard::with_win_condition(players, h, w, rules, mask, win_condition)
+    }
+
+    /// create a fresh board which ends the game by the
+    /// given condition, rather than the one which goes
+    /// with its rules. `players` are given in turn order,
+    /// and the cells in `mask` are blocked for the whole
+    /// game
+    pub fn with_win_condition(players: &[(char, PlayerType)],
+                              h: i32,
+                              w: i32,
+                              rules: Rules,
+                              mask: &[(i32, i32)],
+                              win_condition: Box<dyn WinCondition>)
+                              -> Self {
+        let geometry = Geometry::new((h, w), rules.grid, rules.topology);
+        let state = NogoBoardState::new(players, geometry, mask, rules.hash_seed);
+        let history = PositionHistory::new(state.hash());
+
         NogoBoard {
             height: h,
             width: w,
-            state: NogoBoardState::new(p1, p2, (h, w)),
+            rules,
+            state,
+            history,
+            win_condition,
+            turns: Vec::new(),
+            undone: Vec::new(),
         }
     }
 
-    fn player(&mut self, id: char) -> Option<&mut NogoPlayer> {
-        match self.state.players {
-            (ref mut p1, ref mut p2) => {
-                if p1.id == id {
-                    Some(p1)
-                } else if p2.id == id {
-                    Some(p2)
-                } else {
-                    None
-                }
-            }
-        }
+    pub fn rules(&self) -> &Rules {
+        &self.rules
     }
 
     pub fn height(&self) -> i32 {
@@ -85,177 +214,1068 @@ impl NogoBoard {
         self.state.liberties()
     }
 
+    /// the pieces of the given row of the board, one
+    /// character per cell, or none when the row is off
+    /// the board
+    pub fn row(&self, row: i32) -> Option<String> {
+        if row < 0 || row >= self.height {
+            return None;
+        }
+
+        let start = (row * self.width) as usize;
+        let end = start + self.width as usize;
+
+        Some(self.state.cells[start..end].iter().collect())
+    }
+
+    /// the type of player `id` is
+    pub fn player_type(&self, id: char) -> Option<PlayerType> {
+        self.state.player_kind(id)
+    }
+
+    /// check whether (x, y) lies on the board and is
+    /// neither occupied by any stone nor blocked
+    pub fn is_free(&self, x: i32, y: i32) -> bool {
+        self.state.index(x, y).is_some_and(|cell| self.state.cells[cell] == EMPTY)
+    }
+
+    /// check whether (x, y) is a blocked cell, which
+    /// can never be played on
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.state.index(x, y).is_some_and(|cell| self.state.cells[cell] == BLOCKED)
+    }
+
+    /// the Zobrist hash of the position on the board. A
+    /// position hashes the same whoever is to move, and
+    /// from one run to the next for the same seed
+    pub fn hash(&self) -> u64 {
+        self.state.hash()
+    }
+
+    /// the seed the position is hashed with
+    pub fn hash_seed(&self) -> u64 {
+        self.state.zobrist.seed()
+    }
+
+    /// the rotations and reflections which map the board
+    /// onto itself
+    pub fn symmetries(&self) -> Vec<Transform> {
+        self.state.geometry.symmetries()
+    }
+
+    /// the position in canonical form, the same for every
+    /// position which differs from this one only by a
+    /// symmetry of the board
+    pub fn canonical_position(&self) -> CanonicalPosition {
+        let (cells, transform) = self.state.canonical();
+        let key = self.state.zobrist.hash(cells.iter().cloned());
+
+        CanonicalPosition {
+            rows: cells.chunks(self.width as usize).map(|row| row.iter().collect()).collect(),
+            transform,
+            key,
+        }
+    }
+
+    /// place a stone without any of the rules being
+    /// applied, and without it being recorded in the
+    /// position history
     pub fn update_occupied(&mut self, p: Point) {
         self.state.update_occupied(p);
     }
+
+    /// play a stone for `p.t`. In the variants which take
+    /// captured strings off the board, any strings left
+    /// without liberties are then removed - the opponent's
+    /// first, and then the mover's own. Captured stones are
+    /// credited to the player who captured them
+    pub fn play(&mut self, p: Point) {
+        self.undone.clear();
+        self.apply(Move::Stone(p));
+    }
+
+    fn play_stone(&mut self, p: Point) {
+        self.state.update_occupied(p);
+        self.state.passes = 0;
+        self.state.moves += 1;
+
+        // positions can only repeat, and so only need
+        // to be recorded, when stones are taken off
+        if self.rules.variant.removes_captures() {
+            self.remove_captured(p);
+            self.history.push(self.state.hash());
+        }
+    }
+
+    fn remove_captured(&mut self, p: Point) {
+        let mut captured = 0;
+        for (x, y) in self.state.neighbours(p.x, p.y) {
+            if self.state.stone_at(x, y).is_some_and(|t| t != p.t) &&
+               self.state.liberty_count(x, y) == 0 {
+                captured += self.state.remove_group(x, y);
+            }
+        }
+        self.state.credit_captures(p.t, captured);
+
+        if self.state.liberty_count(p.x, p.y) == 0 {
+            let lost = self.state.remove_group(p.x, p.y);
+            let next = self.state.next_player(p.t);
+            self.state.credit_captures(next, lost);
+        }
+    }
+
+    /// `id` passes instead of playing a stone
+    pub fn pass(&mut self, id: char) {
+        self.undone.clear();
+        self.apply(Move::Pass(id));
+    }
+
+    fn pass_turn(&mut self) {
+        self.state.passes += 1;
+        self.state.moves += 1;
+        self.history.push(self.state.hash());
+    }
+
+    /// `id` resigns and drops out of the game. Its stones
+    /// stay on the board, and the rest play on if more
+    /// than one player is left
+    pub fn resign(&mut self, id: char) {
+        self.undone.clear();
+        self.apply(Move::Resign(id));
+    }
+
+    fn resign_player(&mut self, id: char) {
+        if let Some(p) = self.state.players.iter_mut().find(|p| p.id() == id) {
+            p.eliminated = true;
+        }
+
+        self.state.moves += 1;
+    }
+
+    /// the number of passes played in a row
+    pub fn passes(&self) -> u32 {
+        self.state.passes
+    }
+
+    /// the number of turns played since the game
+    /// started (not counting any setup stones)
+    pub fn moves(&self) -> u32 {
+        self.state.moves
+    }
+
+    /// check whether the swap rule lets `id` take over
+    /// the first stone of the game instead of replying
+    /// to it - only the player right after the first
+    /// mover can, and only on its first turn
+    pub fn can_swap(&self, id: char) -> bool {
+        self.rules.swap && self.state.moves == 1 &&
+        self.last_mover().is_some_and(|mover| self.next_player(mover) == id)
+    }
+
+    /// `id` takes over the first stone of the game, and
+    /// the first mover takes over the pieces of `id`.
+    /// The turn stays with the pieces of `id`, which
+    /// are now played by the first mover
+    pub fn swap(&mut self, id: char) {
+        self.undone.clear();
+        self.apply(Move::Swap(id));
+    }
+
+    fn swap_players(&mut self, id: char) {
+        if let Some(mover) = self.last_mover() {
+            let kinds = (self.state.player_kind(mover), self.state.player_kind(id));
+
+            if let (Some(mover_kind), Some(kind)) = kinds {
+                for p in self.state.players.iter_mut() {
+                    if p.id == mover {
+                        p.kind = kind;
+                    } else if p.id == id {
+                        p.kind = mover_kind;
+                    }
+                }
+            }
+        }
+
+        self.state.moves += 1;
+    }
+
+    /// make a turn, and record it in the move history
+    /// along with the state of the game before it
+    fn apply(&mut self, mov: Move) {
+        self.turns.push(Turn {
+            mov,
+            eliminated: Vec::new(),
+            players: self.state.players.clone(),
+            last_move: self.state.last_move,
+            passes: self.state.passes,
+            moves: self.state.moves,
+            changes: self.state.changes.len(),
+            positions: self.history.len(),
+        });
+
+        match mov {
+            Move::Stone(p) => self.play_stone(p),
+            Move::Pass(_) => self.pass_turn(),
+            Move::Resign(id) => self.resign_player(id),
+            Move::Swap(id) => self.swap_players(id),
+        }
+    }
+
+    /// take back the last turn of the game, along with
+    /// any players it knocked out, and return it. The
+    /// strings and liberties, captures and turn count
+    /// are all put back as they were before the turn,
+    /// and the turn can be made again with `redo`
+    pub fn undo(&mut self) -> Option<Move> {
+        let turn = self.turns.pop()?;
+
+        self.state.rewind(turn.changes);
+        self.state.players = turn.players;
+        self.state.last_move = turn.last_move;
+        self.state.passes = turn.passes;
+        self.state.moves = turn.moves;
+        self.history.truncate(turn.positions);
+
+        self.undone.push((turn.mov, turn.eliminated));
+        Some(turn.mov)
+    }
+
+    /// make the last turn taken back again, along with
+    /// any players it knocked out, and return it
+    pub fn redo(&mut self) -> Option<Move> {
+        let (mov, eliminated) = self.undone.pop()?;
+
+        self.apply(mov);
+        for id in eliminated {
+            self.eliminate(id);
+        }
+
+        Some(mov)
+    }
+
+    /// start the position and move histories afresh
+    /// from the position on the board
+    fn reset_history(&mut self) {
+        self.history = PositionHistory::new(self.state.hash());
+        self.state.changes.clear();
+        self.turns.clear();
+        self.undone.clear();
+    }
+
+    /// check whether there is a turn to take back
+    pub fn can_undo(&self) -> bool {
+        !self.turns.is_empty()
+    }
+
+    /// check whether there is a turn taken back which
+    /// can be made again
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// the turns made so far, in order
+    pub fn move_history(&self) -> Vec<Move> {
+        self.turns.iter().map(|turn| turn.mov).collect()
+    }
+
+    /// the stones taken off the board by the last turn,
+    /// along with those of any players it knocked out
+    pub fn removed_by_last_turn(&self) -> Vec<Point> {
+        let start = self.turns.last().map_or(self.state.changes.len(), |turn| turn.changes);
+
+        self.state.changes[start..]
+            .iter()
+            .filter(|&&(cell, old)| old != EMPTY && old != BLOCKED && self.state.cells[cell] == EMPTY)
+            .map(|&(cell, old)| {
+                let (x, y) = self.state.coords(cell);
+                Point::new(x, y, old)
+            })
+            .collect()
+    }
+
+    /// the last turn made, if any
+    pub fn last_turn(&self) -> Option<Move> {
+        self.turns.last().map(|turn| turn.mov)
+    }
+
+    /// the player to move after the given turn - the
+    /// next player in turn, unless the turn was a swap,
+    /// after which the same player goes on to play a stone
+    pub fn player_after(&self, mov: Move) -> char {
+        match mov {
+            Move::Swap(id) => id,
+            _ => self.next_player(mov.player()),
+        }
+    }
+
+    /// the pieces of all the players, in turn order
+    pub fn player_ids(&self) -> Vec<char> {
+        self.state.players.iter().map(|p| p.id()).collect()
+    }
+
+    /// the pieces of the players still in the game, in
+    /// turn order
+    pub fn active_players(&self) -> Vec<char> {
+        self.state
+            .players
+            .iter()
+            .filter(|p| !p.eliminated)
+            .map(|p| p.id())
+            .collect()
+    }
+
+    /// check whether `id` has dropped out of the game
+    pub fn is_eliminated(&self, id: char) -> bool {
+        self.state.player(id).is_some_and(|p| p.eliminated)
+    }
+
+    /// the player still in the game who moves after `id`
+    pub fn next_player(&self, id: char) -> char {
+        self.state.next_player(id)
+    }
+
+    /// drop `id` out of the game. Its stones are taken
+    /// off the board (and credited to the player who
+    /// made the last move), so that the rest can play on
+    pub fn eliminate(&mut self, id: char) {
+        if let Some(turn) = self.turns.last_mut() {
+            turn.eliminated.push(id);
+        }
+
+        self.state.eliminate(id);
+    }
+
+    /// the player who placed the last stone, if any
+    pub fn last_mover(&self) -> Option<char> {
+        self.state.last_move.map(|p| p.t)
+    }
+
+    /// check whether the last stone placed left any
+    /// string of `id` without liberties
+    pub fn is_captured(&self, id: char) -> bool {
+        self.state.check_captured(id)
+    }
+
+    /// the number of stones `id` has captured
+    pub fn captures(&self, id: char) -> u32 {
+        self.state.player(id).map_or(0, |p| p.captures)
+    }
+
+    /// check whether `id` may play at (x, y) - the
+    /// cell must be free, the move must not capture
+    /// the player's own string unless the rules allow it,
+    /// and it must not repeat a position the ko rule
+    /// forbids
+    pub fn is_legal(&self, x: i32, y: i32, id: char) -> bool {
+        self.is_free(x, y) && (self.self_capture_allowed(id) || !self.is_self_capture(x, y, id)) &&
+        !self.violates_ko(x, y, id)
+    }
+
+    /// check whether `id` playing at (x, y) would repeat
+    /// an earlier position which the ko rule forbids
+    pub fn violates_ko(&self, x: i32, y: i32, id: char) -> bool {
+        // unless stones are taken off the board, no
+        // position can ever repeat
+        if !self.rules.variant.removes_captures() || !self.is_free(x, y) {
+            return false;
+        }
+
+        match self.rules.ko {
+            Ko::None => false,
+            Ko::Simple => {
+                // only a capture can undo the opponent's
+                // last move
+                let (own, opponent) = self.state.capture_effects(x, y, id);
+                (own || opponent) && self.history.is_ko(self.state.position_hash_after(x, y, id))
+            }
+            Ko::Superko => self.history.is_repeat(self.state.position_hash_after(x, y, id)),
+        }
+    }
+
+    /// check whether there is still any move left
+    /// for `id` to play on the board
+    pub fn has_legal_moves(&self, id: char) -> bool {
+        if self.state.free_cells == 0 {
+            return false;
+        }
+
+        if self.self_capture_allowed(id) {
+            return true;
+        }
+
+        self.state
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| t == EMPTY)
+            .any(|(cell, _)| {
+                let (x, y) = self.state.coords(cell);
+                !self.is_self_capture(x, y, id)
+            })
+    }
+
+    /// check whether `id` playing at (x, y) would leave
+    /// its own string without liberties, and so lose the
+    /// game on the spot. A move which also captures an
+    /// opponent string only loses if the rules say so
+    pub fn is_self_capture(&self, x: i32, y: i32, id: char) -> bool {
+        let (own, opponent) = self.state.capture_effects(x, y, id);
+
+        // when captured stones are taken off the board, the
+        // opponent's strings go first, which frees the mover's own
+        let mover_loses = self.rules.variant == Variant::Atari &&
+                          self.rules.both_captured == BothCaptured::MoverLoses;
+
+        own && (!opponent || mover_loses)
+    }
+
+    /// whether `id` may play a move which captures its
+    /// own string
+    fn self_capture_allowed(&self, id: char) -> bool {
+        match self.rules.suicide {
+            Suicide::Allow => true,
+            Suicide::Forbid => false,
+            // a human is warned first, but a computer
+            // player always heeds the warning
+            Suicide::Warn => self.state.player_kind(id) == Some(PlayerType::HUMAN),
+        }
+    }
+
+    /// the identity of the group the stone at (x, y)
+    /// belongs to, if there is a stone there. Two stones
+    /// are part of the same string if and only if they
+    /// have the same group identity. A string is known by
+    /// its first cell, row by row, so it has the same
+    /// identity however the board came to hold it - after
+    /// turns are taken back, say
+    pub fn group_id(&self, x: i32, y: i32) -> Option<usize> {
+        self.state.index(x, y).and_then(|cell| self.state.group_identity(cell))
+    }
+
+    /// all the stones of the group that the stone at (x, y)
+    /// belongs to, row by row. Empty if there is no stone
+    /// there
+    pub fn group_members(&self, x: i32, y: i32) -> Vec<Point> {
+        match self.state.index(x, y) {
+            Some(cell) => self.state.group_points(cell),
+            None => Vec::new(),
+        }
+    }
+
+    /// the piece of the stone at (x, y), if there is one
+    pub fn stone_at(&self, x: i32, y: i32) -> Option<char> {
+        self.state.stone_at(x, y)
+    }
+
+    /// the string the stone at (x, y) belongs to, if there
+    /// is a stone there
+    pub fn group_at(&self, x: i32, y: i32) -> Option<Group> {
+        let cell = self.state.index(x, y)?;
+        let id = self.state.group_identity(cell)?;
+
+        Some(Group::new(id,
+                        self.state.cells[cell],
+                        self.state.group_points(cell),
+                        self.liberties_of_group(x, y)))
+    }
+
+    /// the liberties of the string the stone at (x, y)
+    /// belongs to. Empty if there is no stone there
+    pub fn liberties_of_group(&self, x: i32, y: i32) -> HashSet<Point> {
+        let libs = self.state.index(x, y).and_then(|cell| self.state.groups.liberties(cell));
+
+        libs.map_or_else(HashSet::new, |libs| {
+            libs.iter()
+                .map(|&cell| {
+                    let (r, c) = self.state.coords(cell);
+                    Point::new(r, c, EMPTY)
+                })
+                .collect()
+        })
+    }
+
+    /// every string on the board, in order of their
+    /// identities
+    pub fn groups(&self) -> Vec<Group> {
+        let mut groups = self.state
+            .groups
+            .roots()
+            .into_iter()
+            .filter_map(|root| {
+                let (x, y) = self.state.coords(root);
+                self.group_at(x, y)
+            })
+            .collect::<Vec<_>>();
+
+        groups.sort_by_key(|group| group.id());
+        groups
+    }
+
+    /// check whether the string the stone at (x, y)
+    /// belongs to is down to its last liberty
+    pub fn is_in_atari(&self, x: i32, y: i32) -> bool {
+        self.stone_at(x, y).is_some() && self.state.liberty_count(x, y) == 1
+    }
+
+    /// the strings of `id` which are down to their last
+    /// liberty
+    pub fn groups_in_atari(&self, id: char) -> Vec<Group> {
+        self.groups()
+            .into_iter()
+            .filter(|group| group.owner() == id && group.is_in_atari())
+            .collect()
+    }
+
+    /// every move `id` may play, going through the board
+    /// row by row
+    pub fn legal_moves(&self, id: char) -> impl Iterator<Item = Point> + '_ {
+        (0..self.state.cells.len())
+            .map(move |cell| self.state.coords(cell))
+            .filter(move |&(x, y)| self.is_legal(x, y, id))
+            .map(move |(x, y)| Point::new(x, y, id))
+    }
+
+    /// check whether the stones at `a` and `b` are part of
+    /// the same group
+    pub fn same_group(&self, a: (i32, i32), b: (i32, i32)) -> bool {
+        let root = |(x, y): (i32, i32)| {
+            self.state.index(x, y).and_then(|cell| self.state.groups.find(cell))
+        };
+
+        match (root(a), root(b)) {
+            (Some(ga), Some(gb)) => ga == gb,
+            _ => false,
+        }
+    }
+}
+
+/// a turn in the move history. `eliminated` holds the
+/// players the turn knocked out, and the rest is the
+/// state of the game just before the turn - the players,
+/// the last stone and the counts of passes and moves,
+/// along with how far the log of changed cells and the
+/// position history had got
+#[derive(Debug, Clone)]
+struct Turn {
+    mov: Move,
+    eliminated: Vec<char>,
+    players: Vec<NogoPlayer>,
+    last_move: Option<Point>,
+    passes: u32,
+    moves: u32,
+    changes: usize,
+    positions: usize,
 }
 
 /// this holds the game state by holding
-/// references to the current players of
-/// the game. `all_points` is merely
-/// used for performance reasons
-#[derive(Debug)]
+/// the players of the game, in turn order.
+/// The board itself is a dense,
+/// row-major array of cells holding either
+/// a player's piece or `EMPTY`. `groups`
+/// tracks which stones form a string, and
+/// `last_move` is the most recently placed
+/// stone. `free_cells` is the number of
+/// `EMPTY` cells left, `passes` the number
+/// of passes played in a row, and `moves`
+/// the number of turns played in all.
+/// `geometry` decides how cells are numbered
+/// and which cells are adjacent. `BLOCKED`
+/// cells are left out of the board entirely -
+/// they are nobody's neighbour or liberty.
+/// `changes` logs every cell changed since the
+/// game started, along with what it held before.
+/// `hash` is the Zobrist hash of the cells, kept
+/// up to date with every change
+#[derive(Debug, Clone)]
 struct NogoBoardState {
-    players: (NogoPlayer, NogoPlayer),
-    all_points: HashSet<Point>,
-    occupied_points: HashSet<Point>,
+    players: Vec<NogoPlayer>,
+    geometry: Geometry,
+    cells: Vec<char>,
+    free_cells: usize,
+    groups: GroupTracker,
+    last_move: Option<Point>,
+    passes: u32,
+    moves: u32,
+    changes: Vec<(usize, char)>,
+    zobrist: Zobrist,
+    hash: u64,
 }
 
 impl NogoBoardState {
-    fn new(p1: PlayerType, p2: PlayerType, limits: (i32, i32)) -> Self {
-        let mut state = NogoBoardState {
-            players: (NogoPlayer::new('0', p1), NogoPlayer::new('X', p2)),
-            all_points: HashSet::new(),
-            occupied_points: HashSet::new(),
-        };
-
-        for i in 0..limits.0 {
-            // height
-            for j in 0..limits.1 {
-                // width
-                let point = Point::new(i, j, '.');
-                state.all_points.insert(point.clone());
+    fn new(players: &[(char, PlayerType)],
+           geometry: Geometry,
+           mask: &[(i32, i32)],
+           seed: u64)
+           -> Self {
+        let area = geometry.area();
+        let mut cells = vec![EMPTY; area];
+
+        for &(x, y) in mask {
+            if let Some(cell) = geometry.index(x, y) {
+                cells[cell] = BLOCKED;
             }
         }
 
-        state
+        let free_cells = cells.iter().filter(|&&t| t == EMPTY).count();
+        let zobrist = Zobrist::new(seed);
+        let hash = zobrist.hash(cells.iter().cloned());
+
+        NogoBoardState {
+            players: players.iter().map(|&(id, kind)| NogoPlayer::new(id, kind)).collect(),
+            geometry,
+            cells,
+            free_cells,
+            groups: GroupTracker::new(area),
+            last_move: None,
+            passes: 0,
+            moves: 0,
+            changes: Vec::new(),
+            zobrist,
+            hash,
+        }
     }
 
-    fn players(&self) -> (&NogoPlayer, &NogoPlayer) {
-        (&self.players.0, &self.players.1)
+    fn player(&self, id: char) -> Option<&NogoPlayer> {
+        self.players.iter().find(|p| p.id() == id)
     }
 
-    /// retrieve all the current liberties
-    /// custom retrieval since the default
-    /// HashSet implementation does not allow
-    /// us to check against a custom comparator
-    pub fn liberties(&self) -> HashSet<Point> {
-        let mut free = HashSet::new();
+    /// the player still in the game who moves after
+    /// `id`, going round the turn order
+    fn next_player(&self, id: char) -> char {
+        let n = self.players.len();
+        let pos = self.players.iter().position(|p| p.id() == id).unwrap_or(n - 1);
+
+        (1..n + 1)
+            .map(|i| &self.players[(pos + i) % n])
+            .find(|p| !p.eliminated)
+            .map_or(id, |p| p.id())
+    }
+
+    /// add to the number of stones `id` has captured
+    fn credit_captures(&mut self, id: char, stones: u32) {
+        if let Some(p) = self.players.iter_mut().find(|p| p.id() == id) {
+            p.captures += stones;
+        }
+    }
+
+    fn player_kind(&self, id: char) -> Option<PlayerType> {
+        self.player(id).map(|p| *p.kind())
+    }
+
+    /// mark `id` as out of the game, and take all
+    /// of its strings off the board
+    fn eliminate(&mut self, id: char) {
+        if let Some(p) = self.players.iter_mut().find(|p| p.id() == id) {
+            p.eliminated = true;
+        }
 
-        for point in self.all_points.iter() {
-            if self.occupied().iter().find(|&&p| (p.x, p.y) == (point.x, point.y)).is_none() {
-                free.insert(*point);
+        let mut removed = 0;
+        for cell in 0..self.cells.len() {
+            if self.cells[cell] == id {
+                let (x, y) = self.coords(cell);
+                removed += self.remove_group(x, y);
             }
         }
 
-        free
+        if let Some(last) = self.last_move {
+            if last.t != id {
+                self.credit_captures(last.t, removed);
+            }
+        }
     }
 
-    /// retrieve the current occupied points
-    /// of the board
-    fn occupied(&self) -> &HashSet<Point> {
-        &self.occupied_points
+    /// retrieve all the current liberties, that
+    /// is, every empty cell of the board
+    pub fn liberties(&self) -> HashSet<Point> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| t == EMPTY)
+            .map(|(cell, &t)| {
+                let (x, y) = self.coords(cell);
+                Point::new(x, y, t)
+            })
+            .collect()
     }
 
     /// update the occupied points of the board
-    /// with the new point
+    /// with the new point, take the point away from
+    /// the liberties of the adjacent strings, and
+    /// merge it with every adjacent string of the
+    /// same colour
     fn update_occupied(&mut self, p: Point) {
-        self.occupied_points.insert(p);
+        let cell = match self.index(p.x, p.y) {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        self.set_cell(cell, p.t);
+        self.last_move = Some(p);
+
+        let mut liberties = HashSet::new();
+        let mut friends = Vec::new();
+
+        for (x, y) in self.neighbours(p.x, p.y) {
+            let other = self.index(x, y).unwrap();
+
+            match self.stone_at(x, y) {
+                None => {
+                    liberties.insert(other);
+                }
+                Some(t) => {
+                    self.groups.remove_liberty(other, cell);
+                    if t == p.t {
+                        friends.push(other);
+                    }
+                }
+            }
+        }
+
+        self.groups.add(cell, liberties);
+
+        for other in friends {
+            self.groups.union(cell, other);
+        }
     }
-}
 
-/// this represents a player in the game.
-/// each player holds the vector of "strings"
-/// that he/she owns
-#[derive(Debug)]
-struct NogoPlayer {
-    id: char,
-    strings: Vec<NogoString>,
-    captured: bool,
-    kind: PlayerType,
-}
+    /// map board coordinates to a cell index, if the
+    /// coordinates lie on the board
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        self.geometry.index(x, y)
+    }
 
-impl NogoPlayer {
-    fn new(id: char, typ: PlayerType) -> Self {
-        NogoPlayer {
-            id: id,
-            strings: Vec::new(),
-            captured: false,
-            kind: typ,
+    /// map a cell index back to board coordinates
+    fn coords(&self, cell: usize) -> (i32, i32) {
+        self.geometry.coords(cell)
+    }
+
+    /// the adjacent cells, as laid out by the
+    /// geometry of the board, leaving out any
+    /// blocked cells
+    fn neighbours(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        self.geometry
+            .neighbours(x, y)
+            .into_iter()
+            .filter(|&(r, c)| self.index(r, c).is_some_and(|cell| self.cells[cell] != BLOCKED))
+            .collect()
+    }
+
+    /// the piece at the given coordinates, if any
+    fn stone_at(&self, x: i32, y: i32) -> Option<char> {
+        match self.index(x, y).map(|cell| self.cells[cell]) {
+            Some(EMPTY) | Some(BLOCKED) | None => None,
+            t => t,
         }
     }
 
-    fn id(&self) -> char {
-        self.id
+    /// the stones making up the group of the given cell
+    fn group_points(&self, cell: usize) -> Vec<Point> {
+        let mut members = self.groups.members(cell);
+        members.sort();
+
+        members.into_iter()
+            .filter_map(|member| {
+                let (x, y) = self.coords(member);
+                self.stone_at(x, y).map(|t| Point::new(x, y, t))
+            })
+            .collect()
     }
 
-    fn kind(&self) -> &PlayerType {
-        &self.kind
+    /// the identity of the string on the cell - its first
+    /// cell, row by row - if there is a stone there
+    fn group_identity(&self, cell: usize) -> Option<usize> {
+        self.groups.members(cell).into_iter().min()
+    }
+
+    /// the number of liberties of the string at (x, y)
+    fn liberty_count(&self, x: i32, y: i32) -> usize {
+        self.index(x, y).map_or(0, |cell| self.groups.liberty_count(cell))
     }
 
-    // for each string of the current player, check
-    // if the new coordinates form part of an existing
-    // string. If so, update the string data, otherwise
-    // add a new string
-    fn update_strings(&mut self, pt: Point) {
-        let (x, y) = (pt.x, pt.y);
+    /// take the string at (x, y) off the board, giving
+    /// its cells back as liberties to the strings around
+    /// it, and return the number of stones removed
+    fn remove_group(&mut self, x: i32, y: i32) -> u32 {
+        let cell = match self.index(x, y) {
+            Some(cell) => cell,
+            None => return 0,
+        };
 
-        for string in self.strings.iter_mut() {
-            let (l, r, u, d) = ((x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y));
+        let members = self.groups.remove(cell);
 
-            if string.find(l) || string.find(u) || string.find(r) || string.find(d) {
-                string.add(pt);
-                return;
+        for &member in members.iter() {
+            self.set_cell(member, EMPTY);
+        }
+
+        for &member in members.iter() {
+            let (r, c) = self.coords(member);
+
+            for (nr, nc) in self.neighbours(r, c) {
+                if self.stone_at(nr, nc).is_some() {
+                    let neighbour = self.index(nr, nc).unwrap();
+                    self.groups.add_liberty(neighbour, member);
+                }
             }
         }
 
-        // no match, so add a new string
-        self.strings.push(NogoString::new(pt));
+        members.len() as u32
+    }
+
+    /// put a piece (or `EMPTY`) on the cell, and log
+    /// what the cell held before
+    fn set_cell(&mut self, cell: usize, t: char) {
+        let old = self.cells[cell];
+        self.changes.push((cell, old));
+
+        if old == EMPTY {
+            self.free_cells -= 1;
+        }
+        if t == EMPTY {
+            self.free_cells += 1;
+        }
+
+        self.hash ^= self.zobrist.key(cell, old) ^ self.zobrist.key(cell, t);
+        self.cells[cell] = t;
+    }
+
+    /// the cells as they were when the log of changes
+    /// began
+    fn start_cells(&self) -> Vec<char> {
+        let mut cells = self.cells.clone();
+        for &(cell, old) in self.changes.iter().rev() {
+            cells[cell] = old;
+        }
+
+        cells
     }
 
-    // check if, after the last move, this
-    // player has any of its strings captured -
-    // if none of the components of a string
-    // has any liberties, then the string, and
-    // therefore the player is captured
-    fn check_captured(&self, free: &HashSet<Point>) -> bool {
-        for string in self.strings.iter() {
-            let mut count = 0;
-            for component in string.components.iter() {
-                let (r, c) = (component.x, component.y);
+    /// put back every cell changed since the log of
+    /// changes was `len` long. Only the strings on or
+    /// next to those cells can have changed, so only
+    /// those are taken apart and built up again
+    fn rewind(&mut self, len: usize) {
+        let changes = self.changes.split_off(len);
+
+        let mut touched = HashSet::new();
+        for &(cell, _) in changes.iter() {
+            let (x, y) = self.coords(cell);
+            touched.insert(cell);
+            touched.extend(self.neighbours(x, y).into_iter().map(|(r, c)| self.index(r, c).unwrap()));
+        }
+
+        for &cell in touched.iter() {
+            if self.groups.contains(cell) {
+                self.groups.remove(cell);
+            }
+        }
+
+        for &(cell, old) in changes.iter().rev() {
+            if self.cells[cell] == EMPTY {
+                self.free_cells -= 1;
+            }
+            if old == EMPTY {
+                self.free_cells += 1;
+            }
+
+            self.hash ^= self.zobrist.key(cell, self.cells[cell]) ^ self.zobrist.key(cell, old);
+            self.cells[cell] = old;
+        }
 
-                let (l, r, u, d) = ((r, c - 1), (r, c + 1), (r - 1, c), (r + 1, c));
+        for &cell in touched.iter() {
+            let (x, y) = self.coords(cell);
+            if self.stone_at(x, y).is_some() && !self.groups.contains(cell) {
+                self.rebuild_group(cell);
+            }
+        }
+    }
 
-                if self.find_point(&l, free) || self.find_point(&r, free) ||
-                   self.find_point(&u, free) || self.find_point(&d, free) {
-                    count += 1;
+    /// add the string of stones at the given cell to the
+    /// group tracker from scratch, with all its liberties
+    fn rebuild_group(&mut self, cell: usize) {
+        let t = self.cells[cell];
+        let mut members = vec![cell];
+        let mut seen = HashSet::new();
+        let mut liberties = HashSet::new();
+        seen.insert(cell);
+
+        let mut i = 0;
+        while i < members.len() {
+            let (x, y) = self.coords(members[i]);
+
+            for (r, c) in self.neighbours(x, y) {
+                let other = self.index(r, c).unwrap();
+
+                match self.stone_at(r, c) {
+                    None => {
+                        liberties.insert(other);
+                    }
+                    Some(u) if u == t && seen.insert(other) => members.push(other),
+                    _ => {}
                 }
             }
 
-            if count == 0 {
-                return true;
+            i += 1;
+        }
+
+        self.groups.add(cell, liberties);
+        for &member in members.iter().skip(1) {
+            self.groups.add(member, HashSet::new());
+            self.groups.union(cell, member);
+        }
+    }
+
+    /// the Zobrist hash of the position on the board
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// the cells of the board once transformed
+    fn transformed(&self, transform: Transform) -> Vec<char> {
+        let limits = self.geometry.limits();
+        let mut cells = vec![EMPTY; self.cells.len()];
+
+        for (cell, &t) in self.cells.iter().enumerate() {
+            let (x, y) = self.coords(cell);
+            let p = transform.apply(Point::new(x, y, t), limits);
+            cells[self.index(p.x, p.y).unwrap()] = t;
+        }
+
+        cells
+    }
+
+    /// the least of the images of the board under its
+    /// symmetries, and the transform which gives it
+    fn canonical(&self) -> (Vec<char>, Transform) {
+        self.geometry
+            .symmetries()
+            .into_iter()
+            .map(|transform| (self.transformed(transform), transform))
+            .min()
+            .unwrap()
+    }
+
+    /// the hash of the position after `id` plays at (x, y)
+    /// under the full Go rules, worked out without playing
+    /// the move - the opponent's strings whose last liberty
+    /// is (x, y) are taken off, or failing that, the mover's
+    /// own string if it would be left without liberties
+    fn position_hash_after(&self, x: i32, y: i32, id: char) -> u64 {
+        let cell = self.index(x, y).unwrap();
+        let (own, opponent) = self.capture_effects(x, y, id);
+        let suicide = own && !opponent;
+
+        let mut removed = HashSet::new();
+        if suicide {
+            removed.insert(cell);
+        }
+
+        for (r, c) in self.neighbours(x, y) {
+            let neighbour = self.index(r, c).unwrap();
+
+            match self.stone_at(r, c) {
+                Some(t) if t != id && self.groups.liberty_count(neighbour) == 1 => {
+                    removed.extend(self.groups.members(neighbour));
+                }
+                Some(t) if t == id && suicide => removed.extend(self.groups.members(neighbour)),
+                _ => {}
             }
         }
 
-        false
+        let mut hash = self.hash;
+        if !suicide {
+            hash ^= self.zobrist.key(cell, id);
+        }
+
+        // (x, y) itself is still empty, and adds nothing
+        for &i in removed.iter() {
+            hash ^= self.zobrist.key(i, self.cells[i]);
+        }
+
+        hash
     }
 
-    fn find_point(&self, needle: &(i32, i32), haystack: &HashSet<Point>) -> bool {
-        match haystack.iter().find(|p| (p.x, p.y) == *needle) {
-            Some(_) => true,
-            _ => false,
+    /// check whether (x, y) is an empty cell surrounded
+    /// only by stones of `id`, which would be pointless
+    /// for `id` to fill in
+    fn is_eye(&self, x: i32, y: i32, id: char) -> bool {
+        self.stone_at(x, y).is_none() &&
+        self.neighbours(x, y).into_iter().all(|(r, c)| self.stone_at(r, c) == Some(id))
+    }
+
+    /// work out which strings would be left without
+    /// liberties if `id` were to play at (x, y): the
+    /// mover's own, and/or any of the opponent's
+    fn capture_effects(&self, x: i32, y: i32, id: char) -> (bool, bool) {
+        let (mut own_liberty, mut opponent_captured) = (false, false);
+
+        for (r, c) in self.neighbours(x, y) {
+            let cell = self.index(r, c).unwrap();
+
+            match self.stone_at(r, c) {
+                None => own_liberty = true,
+                // a liberty other than (x, y) remains
+                Some(t) if t == id => own_liberty |= self.groups.liberty_count(cell) > 1,
+                Some(_) => opponent_captured |= self.groups.liberty_count(cell) == 1,
+            }
         }
+
+        (!own_liberty, opponent_captured)
+    }
+
+    /// check if, after the last move, the given
+    /// player has any of its strings captured -
+    /// if a string has no liberties left, then the
+    /// string, and therefore the player is captured.
+    /// Only the strings touching the last move can
+    /// have lost a liberty, so only those are checked
+    fn check_captured(&self, id: char) -> bool {
+        let last = match self.last_move {
+            Some(p) => p,
+            None => return false,
+        };
+
+        let mut touched = self.neighbours(last.x, last.y);
+        touched.push((last.x, last.y));
+
+        touched.into_iter().any(|(x, y)| {
+            self.stone_at(x, y) == Some(id) &&
+            self.groups.liberty_count(self.index(x, y).unwrap()) == 0
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct NogoString {
-    components: Vec<Point>,
+/// this represents a player in the game.
+/// the strings a player owns are tracked
+/// by the board itself. `captures` is the
+/// tally of opponent stones taken off the
+/// board by the player (in the variants
+/// which take captured stones off), and
+/// `eliminated` is set once the player has
+/// dropped out of a game of more than two
+#[derive(Debug, Clone)]
+struct NogoPlayer {
+    id: char,
+    kind: PlayerType,
+    captures: u32,
+    eliminated: bool,
 }
 
-impl NogoString {
-    fn new(p: Point) -> Self {
-        let mut string = NogoString { components: Vec::new() };
-        string.components.push(p);
-        string
+impl NogoPlayer {
+    fn new(id: char, typ: PlayerType) -> Self {
+        NogoPlayer {
+            id,
+            kind: typ,
+            captures: 0,
+            eliminated: false,
+        }
     }
 
-    fn find(&self, t: (i32, i32)) -> bool {
-        self.components.iter().find(|&&p| (p.x, p.y) == (t.0, t.1)).is_some()
+    fn id(&self) -> char {
+        self.id
     }
 
-    fn add(&mut self, p: Point) {
-        self.components.push(p);
+    fn kind(&self) -> &PlayerType {
+        &self.kind
     }
 }
 
@@ -269,7 +1289,7 @@ pub struct Point {
 
 impl Point {
     pub fn new(x: i32, y: i32, t: char) -> Self {
-        Point { x: x, y: y, t: t }
+        Point { x, y, t }
     }
 
     pub fn x(&self) -> i32 {
@@ -284,212 +1304,306 @@ impl Point {
 }
 
 
-///
-/// Start a fresh game
-///
-pub fn start_new_game<'a>(p1: &'a str, p2: &'a str, height: &'a str, width: &'a str) {
-    // check if the arguments are correct
-    match eh::validation::validate_new_game_parameters(p1, p2, height, width) {
-        Ok((p1, p2, h, w)) => {
-            let mut board = create_board(&p1, &p2, h, w);
-            game_loop(&p1, &p2, PLAYER_ZERO, &mut board);
+/// place the setup stones and then the handicap stones
+/// of the first player on a fresh board, and return the
+/// player to move first - the first player, or the one
+/// after it when it has taken a handicap. The stones
+/// are placed the same way as the moves of a saved game
+/// are replayed
+fn set_up_board<'a>(board: &mut NogoBoard,
+                    setup: Vec<Point>,
+                    handicap: usize)
+                    -> eh::Result<'a, char> {
+    let first = board.state.players[0].id();
+    replay_moves(setup, board);
+
+    if handicap > 0 {
+        let points = handicap::handicap_points(board.height, board.width, handicap);
+
+        if points.len() < handicap || points.iter().any(|&(x, y)| !board.is_free(x, y)) {
+            return Err(eh::construct_error("not enough free star points for the handicap stones",
+                                           eh::NogoErrorKind::InvalidSetup));
         }
 
-        Err(e) => eh::exit_with_error(e),
+        replay_moves(points.into_iter().map(|(x, y)| Point::new(x, y, first)).collect(),
+                     board);
     }
-}
 
-/// factoring out the game loop so that it can be used with
-/// both a new game as well as continuing from a saved game
-fn game_loop(p1: &PlayerType, p2: &PlayerType, start_player: char, board: &mut NogoBoard) {
-    let first_player_type = if start_player == PLAYER_ZERO { p1 } else { p2 };
-    let second_player_type = if first_player_type == p1 { p2 } else { p1 };
+    let captured = board.state
+        .groups
+        .roots()
+        .into_iter()
+        .any(|root| board.state.groups.liberty_count(root) == 0);
 
-    let other_player = if start_player == PLAYER_ZERO { PLAYER_ONE } else { PLAYER_ZERO };
-    
-    loop {
-        display_board(&board);
+    if captured {
+        return Err(eh::construct_error("setup leaves a string without liberties",
+                                       eh::NogoErrorKind::InvalidSetup));
+    }
 
-        {
-            update_board(start_player, &first_player_type, board);
-        }
+    // the game starts from the set up position, with no
+    // move made as yet
+    board.state.last_move = None;
+    board.reset_history();
 
-        display_board(&board);
+    Ok(if handicap > 0 { board.next_player(first) } else { first })
+}
 
-        check_winner(&board);
+/// recreate the board from the contents of a save
+/// file, along with the player who has the next move.
+/// The turns of the game are played again one by one
+/// from the position they started from, and must end
+/// up at the saved board. Older save files hold only
+/// the board, whose stones are simply put back
+pub fn restore_board<'a>(game_data: &[String]) -> eh::Result<'a, (NogoBoard, char)> {
+    restore_game(game_data).map(|(board, current, _)| (board, current))
+}
 
-        {
-            update_board(other_player, &second_player_type, board);
+/// recreate the board and the player to move from the
+/// contents of a save file, as `restore_board` does,
+/// along with the clocks of a timed game
+fn restore_game<'a>(game_data: &[String]) -> eh::Result<'a, (NogoBoard, char, Vec<Clock>)> {
+    // load the metadata
+    let metadata = match game_data.first() {
+        Some(line) => line.split_whitespace().collect::<Vec<_>>(),
+        None => {
+            return Err(eh::construct_error("empty save file",
+                                           eh::NogoErrorKind::ErrorReadingGameFile))
         }
+    };
 
-        check_winner(&board);
-    }
-} // game loop
-
+    let meta = io::parse_save_file_metadata(&metadata)?;
 
-/// Continue the game as saved in the save file.
-/// The way this will work is as follows -
-/// load the game metadata from the saved file,
-/// and recreate the state of the game from the
-/// board positions. Then continue the game
-/// from that juncture, with the player who had
-/// saved the file getting the first move in the
-/// new game
-pub fn continue_saved_game(save_file: &str) {
-    let mut game_data = Vec::new(); // just to satisfy the damned checker
+    // recreate the game state
+    let game_data = game_data.iter().skip(1).collect::<Vec<_>>();
+    let start = game_data.iter().position(|line| line.starts_with("start"));
+    let (rows, history) = game_data.split_at(start.unwrap_or(game_data.len()));
 
-    match io::load_game_state(save_file) {
-        Ok(data) => game_data = data,
-        Err(e) => eh::exit_with_error(e),
-    }
+    let ids = meta.players.iter().map(|&(id, _)| id).collect::<Vec<_>>();
+    io::check_board_rows(rows, meta.height, meta.width)?;
+    let (stones, mask) = io::parse_player_strings_from_saved_file(rows, &ids)?;
 
-    // load the metadata
-    let metadata = &game_data[0]
-        .split_whitespace()
-        .collect::<Vec<_>>();
+    if history.is_empty() {
+        // replay the game moves till the current point,
+        // and start the history of the game afresh from
+        // the restored position
+        let mut board = create_board(&meta.players, meta.height, meta.width, meta.rules, &mask);
 
-    let (mut height, mut width, mut p1type, mut p2type, mut curr_player) =
-        (0, 0, PlayerType::NONE, PlayerType::NONE, ' ');
+        replay_moves(stones, &mut board);
+        set_progress(&mut board, &meta.progress);
+        board.reset_history();
 
-    match io::parse_save_file_metadata(metadata) {
-        Ok((h, w, p1, p2, c)) => {
-            height = h;
-            width = w;
-            p1type = p1;
-            p2type = p2;
-            curr_player = c;
-        }
+        return Ok((board, meta.curr_player, meta.clocks));
+    }
 
-        Err(e) => eh::exit_with_error(e),
+    // the turns are played by the players as they were
+    // when the turns began, which older save files do
+    // not give
+    let saved = io::parse_save_file_history(history, &ids, meta.height, meta.width)?;
+    let players = saved.players.as_ref().unwrap_or(&meta.players);
+    let mut board = create_board(players, meta.height, meta.width, meta.rules, &mask);
+
+    replay_moves(saved.stones, &mut board);
+    set_progress(&mut board, &saved.progress);
+    board.reset_history();
+    replay_turns(saved.turns, &mut board)?;
+
+    let matches = rows.len() == board.height as usize &&
+                  rows.iter()
+                      .enumerate()
+                      .all(|(i, row)| board.row(i as i32).as_ref() == Some(*row)) &&
+                  progress(&board) == meta.progress &&
+                  board.state.players.iter().map(|p| (p.id, p.kind)).eq(meta.players.iter().cloned());
+
+    if !matches {
+        return Err(eh::construct_error("move history does not match the saved board",
+                                       eh::NogoErrorKind::ErrorReadingGameFile));
     }
 
-    // recreate the game state
-    let mut board = create_board(&p1type, &p2type, height, width);
-    let game_data = game_data.iter().skip(1).collect::<Vec<_>>();
-    let (mut player_0_strings, mut player_1_strings) = (Vec::new(), Vec::new());
+    Ok((board, meta.curr_player, meta.clocks))
+}
 
-    match io::parse_player_strings_from_saved_file(&game_data) {
-        Ok((p1_strs, p2_strs)) => {
-            player_0_strings = p1_strs;
-            player_1_strings = p2_strs;
-        }
-        Err(e) => eh::exit_with_error(e),
+
+/// set the passes, turns, captures and players out
+/// of a restored board
+fn set_progress(board: &mut NogoBoard, progress: &io::Progress) {
+    board.state.passes = progress.passes;
+    board.state.moves = progress.moves;
+    for (player, &captures) in board.state.players.iter_mut().zip(progress.captures.iter()) {
+        player.captures = captures;
+        player.eliminated = progress.eliminated.contains(&player.id);
     }
+}
 
-    // replay the game moves till the current point
-    replay_moves(PLAYER_ZERO, player_0_strings, &mut board);
-    replay_moves(PLAYER_ONE, player_1_strings, &mut board);
 
-    // continue the game
-    game_loop(&p1type, &p2type, curr_player, &mut board);
+/// how far the game on the board has got
+fn progress(board: &NogoBoard) -> io::Progress {
+    progress_of(&board.state.players, board.state.passes, board.state.moves)
 }
 
-
-fn replay_moves(player: char, moves: Vec<Point>, board: &mut NogoBoard) {
-    for mov in moves.iter() {
-        board.update_occupied(*mov);
-        // inefficiency here due to Rust's demented checker
-        board.player(player).unwrap().update_strings(*mov);
+fn progress_of(players: &[NogoPlayer], passes: u32, moves: u32) -> io::Progress {
+    io::Progress {
+        passes,
+        moves,
+        captures: players.iter().map(|p| p.captures).collect(),
+        eliminated: players.iter().filter(|p| p.eliminated).map(|p| p.id()).collect(),
     }
 }
 
 
-///
-/// Game logic related functions
-///
+/// play the turns of a saved game again in order, with
+/// the players each of them knocked out, checking that
+/// every stone is a legal move
+fn replay_turns<'a>(turns: Vec<(Move, Vec<char>)>, board: &mut NogoBoard) -> eh::Result<'a, ()> {
+    for (mov, eliminated) in turns {
+        match mov {
+            Move::Stone(p) if board.is_legal(p.x, p.y, p.t) => board.play(p),
+            Move::Pass(id) => board.pass(id),
+            Move::Resign(id) => board.resign(id),
+            Move::Swap(id) if board.can_swap(id) => board.swap(id),
+            _ => {
+                return Err(eh::construct_error("illegal move in save file history",
+                                               eh::NogoErrorKind::ErrorReadingGameFile))
+            }
+        }
 
-/// create a fresh board with the given dimensions
-fn create_board(p1: &PlayerType, p2: &PlayerType, h: i32, w: i32) -> NogoBoard {
-    NogoBoard::new(*p1, *p2, h, w)
-}
+        for id in eliminated {
+            board.eliminate(id);
+        }
+    }
 
-/// display the current state of the board
-fn display_board(board: &NogoBoard) {
-    print_head(board.width);
-    print_rows(board);
-    print_tail(board.width);
+    Ok(())
 }
 
-fn print_head(n: i32) {
-    print!("/");
 
-    for _ in 0..n {
-        print!("-");
+fn replay_moves(moves: Vec<Point>, board: &mut NogoBoard) {
+    for mov in moves {
+        board.update_occupied(mov);
     }
-    println!("\\");
 }
 
-/// collect the string coordinates for both players
-/// in one go so that a single pass will be sufficient
-/// to display the board
-fn print_rows(board: &NogoBoard) {
-    let points = board.state.occupied();
 
-    for i in 0..board.height {
-        print!("|");
+//
+// Game logic related functions
+//
 
-        for j in 0..board.width {
-            let point = points.iter().find(|&&t| (t.x, t.y) == (i, j));
-            if let Some(val) = point {
-                print!("{}", val.t);
-            } else {
-                print!(".");
-            }
+/// create a fresh board with the given players,
+/// dimensions, rules and blocked cells
+fn create_board(players: &[(char, PlayerType)],
+                h: i32,
+                w: i32,
+                rules: Rules,
+                mask: &[(i32, i32)])
+                -> NogoBoard {
+    NogoBoard::new(players, h, w, rules, mask)
+}
+
+/// the move the computer plays for `p` - it takes over
+/// the first stone when the swap rule lets it and the
+/// stone is worth having, resigns or passes when it has no
+/// good move left, and otherwise plays a stone
+fn computer_move(board: &NogoBoard, p: char) -> Move {
+    if board.can_swap(p) && should_swap(board) {
+        Move::Swap(p)
+    } else if should_resign(board, p) {
+        Move::Resign(p)
+    } else if should_pass(board, p) {
+        Move::Pass(p)
+    } else {
+        match get_next_valid_move(board, p) {
+            Some((x, y)) => Move::Stone(Point::new(x, y, p)),
+            None => Move::Pass(p),
         }
-        println!("|");
     }
+}
+
 
+/// the computer takes over the first stone of the
+/// game when it lies in the middle third of the board,
+/// where it is strongest
+fn should_swap(board: &NogoBoard) -> bool {
+    let middle = |n: i32, size: i32| n >= size / 3 && n < size - size / 3;
+
+    board.state
+        .last_move
+        .is_some_and(|p| middle(p.x, board.height) && middle(p.y, board.width))
 }
 
-fn print_tail(n: i32) {
-    print!("\\");
 
-    for _ in 0..n {
-        print!("-");
+/// the computer passes when it has no good move left -
+/// under the full Go rules once the only moves left would
+/// fill in its own eyes or take its own stones off the
+/// board, and in a capture game once every move left
+/// would only hand its stones to the opponent
+fn should_pass(board: &NogoBoard, p: char) -> bool {
+    match board.rules().variant {
+        Variant::Go => sensible_moves(board, p).next().is_none(),
+        Variant::Capture => !has_safe_move(board, p),
+        Variant::Atari => false,
     }
-    println!("/\n");
 }
 
-///
-/// update the board state with a player move.
-/// the player can be a computer or a human -
-/// accept input or generate moves accordingly
-fn update_board(p_id: char, p_type: &PlayerType, board: &mut NogoBoard) {
-    if p_type == &PlayerType::HUMAN {
-        let player_input = io::get_player_move(&board, p_id);
+/// in Atari Go the computer resigns once every move left
+/// would capture its own string, and so lose the game
+/// anyway
+fn should_resign(board: &NogoBoard, p: char) -> bool {
+    board.rules().variant == Variant::Atari && !has_safe_move(board, p)
+}
 
-        match player_input {
-            PlayerInput::Save(path) => save_game_and_exit(&path, &board, p_id),
-            PlayerInput::Point(x, y) => update_board_with_move(p_id, x, y, board),
-        }
-    } else {
-        let (x, y) = get_next_valid_move(&board, p_id);
-        update_board_with_move(p_id, x, y, board);
-    }
+/// check whether `p` has a legal move left which does
+/// not capture its own string
+fn has_safe_move(board: &NogoBoard, p: char) -> bool {
+    board.legal_moves(p).any(|mv| !board.is_self_capture(mv.x, mv.y, p))
 }
 
+/// a move the computer is willing to play. In Go the
+/// computer would rather pass than fill its own eyes,
+/// capture itself, or take the game back to an earlier
+/// position (which could go round in circles forever)
+fn is_sensible_move(board: &NogoBoard, mv: (i32, i32), p: char) -> bool {
+    if !eh::validation::validate_user_move(board, mv, p) {
+        return false;
+    }
+
+    board.rules().variant != Variant::Go ||
+    !(board.state.is_eye(mv.0, mv.1, p) || board.is_self_capture(mv.0, mv.1, p) ||
+      board.history.is_repeat(board.state.position_hash_after(mv.0, mv.1, p)))
+}
 
-fn update_board_with_move(p_id: char, r: i32, c: i32, board: &mut NogoBoard) {
-    let point = Point::new(r, c, p_id);
 
-    board.update_occupied(point.clone());
+/// the moves the computer is willing to play for `p`,
+/// row by row
+fn sensible_moves(board: &NogoBoard, p: char) -> impl Iterator<Item = (i32, i32)> + '_ {
+    board.state
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|&(_, &t)| t == EMPTY)
+        .map(move |(cell, _)| board.state.coords(cell))
+        .filter(move |&mv| is_sensible_move(board, mv, p))
+}
 
-    {
-        let player = board.player(p_id).unwrap();
+/// the starting row and column and the step of the
+/// move generator of each computer player, by their
+/// place in the turn order. Players after the first
+/// two carry on from the second player's values
+fn move_generator_seed(board: &NogoBoard, p: char) -> (i32, i32, i32) {
+    let place = board.state.players.iter().position(|player| player.id() == p).unwrap_or(0) as i32;
 
-        // update the strings of the player
-        player.update_strings(point);
+    match place {
+        0 => (IR0, IC0, F0),
+        _ => (IRX + place - 1, ICX + place - 1, FX + 2 * (place - 1)),
     }
 }
 
-
 /// generate the moves for the computer as per
-/// the given algorithm. this will loop until
-/// a valid move is found
-fn get_next_valid_move(board: &NogoBoard, p: char) -> (i32, i32) {
-    let ir = if p == '0' { IR0 } else { IRX };
-    let ic = if p == '0' { IC0 } else { ICX };
-    let f = if p == '0' { F0 } else { FX };
+/// the given algorithm. The generator can keep
+/// landing on the same few cells (when the board
+/// is as wide as its step, say), so once it has
+/// tried five times as many cells as the board has
+/// without finding a valid move, the first sensible
+/// move on the board is played instead
+fn get_next_valid_move(board: &NogoBoard, p: char) -> Option<(i32, i32)> {
+    let (ir, ic, f) = move_generator_seed(board, p);
 
     let gw = board.width();
     let gh = board.height();
@@ -498,11 +1612,9 @@ fn get_next_valid_move(board: &NogoBoard, p: char) -> (i32, i32) {
     let mut c = ic;
     let b = ir * gw + ic;
 
-    let mut m = 0;
     let mut n;
 
-    loop {
-        m += 1;
+    for m in 1..=5 * gh * gw {
 
         let (mut x, mut y) = match m % 5 {
             0 => {
@@ -540,88 +1652,122 @@ fn get_next_valid_move(board: &NogoBoard, p: char) -> (i32, i32) {
         x %= gh;
         y %= gw;
 
-        if eh::validation::validate_user_move(board, (x, y)) {
-            println!("Player {}: {} {}", p, x, y);
-            return (x, y);
+        if is_sensible_move(board, (x, y), p) {
+            return Some((x, y));
         }
     }
-}
-
 
-/// Save the game - first retrieve the current game state in proper form
-/// and then save it to the save file. Then quite the game gracefully
-fn save_game_and_exit(save_file: &str, board: &NogoBoard, curr_player: char) {
-    let game_data = get_current_game_state(&board, curr_player);
-
-    match io::save_game_state(save_file, game_data) {
-        Ok(_) => {
-            println!("\nFinished saving current game state to file {}", save_file);
-            eh::clean_exit();
-        }
-        Err(e) => eh::exit_with_error(e),
-    }
+    sensible_moves(board, p).next()
 }
 
+
 /// format of the save file -
-/// metadata: h w p1type p2type pturn
+/// metadata: h w pturn players=id:type,... [rule=value ...] passes=n moves=n
+///           captures=n,... [eliminated=id,...] [clocks=ms[/periods],...]
 /// newline
 /// board state
 /// newline
-fn get_current_game_state(board: &NogoBoard, curr_player: char) -> Vec<String> {
+/// start players=id:type,... passes=n moves=n captures=n,... [eliminated=id,...]
+/// newline
+/// board state when the turns began
+/// newline
+/// history turn ...
+/// newline
+fn get_current_game_state(board: &NogoBoard, curr_player: char, clocks: &[Clock]) -> Vec<String> {
     let mut data = Vec::new();
 
     // metadata
-    data.push(format!("{} {} {} {} {}",
+    data.push(format!("{} {} {} {} {} {}",
                       board.height(),
                       board.width(),
-                      if board.state.players().0.kind() == &PlayerType::HUMAN {
-                          'h'
-                      } else {
-                          'c'
-                      },
-                      if board.state.players().1.kind() == &PlayerType::HUMAN {
-                          'h'
-                      } else {
-                          'c'
-                      },
-                      curr_player));
+                      curr_player,
+                      player_entry(&board.state.players),
+                      board.rules().options().join(" "),
+                      progress_entries(&progress(board))));
+
+    if !clocks.is_empty() {
+        data[0].push_str(&format!(" {}", clock_entry(clocks)));
+    }
 
     // actual board (sans borders)
-    let mut line;
-    let points = board.state.occupied();
-
-    for i in 0..board.height {
-        line = String::new();
-        for j in 0..board.width {
-            let point = points.iter().find(|&&t| (t.x, t.y) == (i, j));
-            if let Some(val) = point {
-                line.push(val.t);
-            } else {
-                line.push('.');
-            }
-        }
-        data.push(line);
+    data.extend((0..board.height).filter_map(|i| board.row(i)));
+
+    // the position the turns began from, with the players
+    // as they were then (a swap changes who plays which
+    // pieces), and the turns
+    let (players, start) = match board.turns.first() {
+        Some(turn) => (&turn.players, progress_of(&turn.players, turn.passes, turn.moves)),
+        None => (&board.state.players, progress(board)),
+    };
+    data.push(format!("start {} {}", player_entry(players), progress_entries(&start)));
+
+    let cells = board.state.start_cells();
+    for row in cells.chunks(board.width as usize) {
+        data.push(row.iter().collect());
     }
+
+    let mut history = vec![String::from("history")];
+    for turn in board.turns.iter() {
+        history.push(match turn.mov {
+            Move::Stone(p) => format!("{}:{},{}", p.t, p.x, p.y),
+            Move::Pass(id) => format!("{}:pass", id),
+            Move::Resign(id) => format!("{}:resign", id),
+            Move::Swap(id) => format!("{}:swap", id),
+        });
+
+        history.extend(turn.eliminated.iter().map(|id| format!("{}:out", id)));
+    }
+    data.push(history.join(" "));
+
     data
 }
 
+/// the players of a game and their types in save
+/// file form
+fn player_entry(players: &[NogoPlayer]) -> String {
+    let players = players.iter()
+        .map(|p| format!("{}:{}", p.id(), if p.kind() == &PlayerType::HUMAN { 'h' } else { 'c' }))
+        .collect::<Vec<_>>();
 
+    format!("players={}", players.join(","))
+}
 
-/// check if a winner can be established
-/// to do this, the basic rules of the game
-/// must be checked to see if any string
-/// of either player has been captured
-fn check_winner(board: &NogoBoard) {
-    let free_points = board.state.liberties();
-    let (p1, p2) = board.state.players();
-
-    if p1.check_captured(&free_points) {
-        display_board(&board);
-        println!("Player {} wins!", p2.id());
-        eh::clean_exit();
-    } else if p2.check_captured(&free_points) {
-        display_board(&board);
-        println!("Player {} wins!", p1.id());
-        eh::clean_exit();
+/// the progress of a game in save file form
+fn progress_entries(progress: &io::Progress) -> String {
+    let captures = progress.captures.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let mut entries = format!("passes={} moves={} captures={}",
+                              progress.passes,
+                              progress.moves,
+                              captures.join(","));
+
+    if !progress.eliminated.is_empty() {
+        let eliminated = progress.eliminated.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        entries.push_str(&format!(" eliminated={}", eliminated.join(",")));
     }
+
+    entries
+}
+
+/// the clocks of a timed game in save file form
+fn clock_entry(clocks: &[Clock]) -> String {
+    let clocks = clocks.iter()
+        .map(|clock| {
+            let remaining = clock.remaining().as_millis();
+            match clock.time_control() {
+                rules::TimeControl::ByoYomi { .. } => format!("{}/{}", remaining, clock.periods()),
+                _ => remaining.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("clocks={}", clocks.join(","))
+}
+
+
+
+/// the outcome of the game as it stands, if it
+/// has ended, as decided by the win condition of
+/// the board
+pub fn game_outcome(board: &NogoBoard) -> Option<Outcome> {
+    board.win_condition.outcome(board)
 }
@@ -0,0 +1,38 @@
+//! This submodule lets other code follow a game as it is
+//! played.
+//!
+//! A `GameObserver` is told about everything that happens in a
+//! game - its start, every move, strings put in atari, stones
+//! captured, the game being saved, and its end. Any number of
+//! observers can be added to a `Game`, and each is told in the
+//! order it was added. Every callback does nothing by default,
+//! so an observer only needs to handle the events it wants.
+
+use std::fmt;
+use super::{Game, Move, Outcome, Point};
+use super::groups::Group;
+
+
+/// something which follows a game, such as a logger or
+/// a user interface
+pub trait GameObserver: fmt::Debug {
+    /// the game is about to start, with the board as set up
+    fn game_started(&mut self, _game: &Game) {}
+
+    /// `mov` has just been played
+    fn move_played(&mut self, _game: &Game, _mov: Move) {}
+
+    /// the last move has left the string down to its last
+    /// liberty
+    fn put_in_atari(&mut self, _game: &Game, _group: &Group) {}
+
+    /// the last move has captured the stones - taken off
+    /// the board, or left on it in an atari game
+    fn captured(&mut self, _game: &Game, _stones: &[Point]) {}
+
+    /// the game has been saved to the file at `path`
+    fn saved(&mut self, _game: &Game, _path: &str) {}
+
+    /// the last move has ended the game
+    fn game_over(&mut self, _game: &Game, _outcome: Outcome) {}
+}
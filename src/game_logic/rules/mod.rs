@@ -0,0 +1,218 @@
+//! This submodule holds the configurable rules of a game.
+//!
+//! Rules are given on the command line as `--name=value`
+//! options and are stored in the save file metadata as
+//! `name=value` entries, so a resumed game is played under
+//! the same rules it was started with.
+
+use std::fmt;
+use std::time::Duration;
+use super::geometry::Grid;
+use super::topology::Topology;
+use super::zobrist;
+
+/// the game being played - Atari Go (nogo), which ends
+/// on the first capture, full Go, where captured strings
+/// are taken off the board and the game is decided by score,
+/// or the capture variant of Atari Go, where captured strings
+/// are taken off the board and the first player to capture
+/// a set number of stones wins
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Variant {
+    Atari,
+    Go,
+    Capture,
+}
+
+impl Variant {
+    /// check whether captured strings are taken off the
+    /// board (rather than ending the game)
+    pub fn removes_captures(&self) -> bool {
+        *self != Variant::Atari
+    }
+}
+
+/// how a full Go game is scored
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scoring {
+    Area,
+    Territory,
+}
+
+/// which repeated positions are forbidden. Positions can
+/// only repeat in the variants which take captured stones
+/// off the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ko {
+    None,
+    Simple,
+    Superko,
+}
+
+/// what happens when the board has no legal moves left -
+/// a draw, or a loss for the player to move, who has
+/// nowhere to play
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FullBoard {
+    Draw,
+    PlayerToMoveLoses,
+}
+
+/// who wins when a move leaves strings of both players
+/// without liberties
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BothCaptured {
+    MoverWins,
+    MoverLoses,
+}
+
+/// what to do with a move that captures the mover's own
+/// string, and so loses the game on the spot
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Suicide {
+    Allow,
+    Forbid,
+    Warn,
+}
+
+/// the time each player has for the game. `Absolute`
+/// gives a fixed amount for the whole game, `Fischer`
+/// adds an increment to a player's clock after each of
+/// its turns, and `ByoYomi` follows the main time with a
+/// number of periods - a turn played within a period
+/// keeps it, and each period used up in full is lost
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeControl {
+    None,
+    Absolute { main: Duration },
+    Fischer { main: Duration, increment: Duration },
+    ByoYomi {
+        main: Duration,
+        periods: u32,
+        period: Duration,
+    },
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeControl::None => write!(f, "none"),
+            TimeControl::Absolute { main } => write!(f, "absolute:{}", main.as_secs()),
+            TimeControl::Fischer { main, increment } => {
+                write!(f, "fischer:{}+{}", main.as_secs(), increment.as_secs())
+            }
+            TimeControl::ByoYomi { main, periods, period } => {
+                write!(f, "byo-yomi:{}+{}x{}", main.as_secs(), periods, period.as_secs())
+            }
+        }
+    }
+}
+
+/// `scoring` and `komi` only apply to the full Go variant,
+/// `capture_target` only to the capture variant, `ko` to both
+/// of them, and `both_captured` only to Atari Go. The komi is
+/// added to the score of the second player. `grid` and
+/// `topology` are the shape of the board, and `swap` lets the
+/// second player take over the first stone, for every variant.
+/// `time` is how long each player has to play, and `hash_seed`
+/// the seed the positions of the game are hashed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub variant: Variant,
+    pub full_board: FullBoard,
+    pub both_captured: BothCaptured,
+    pub suicide: Suicide,
+    pub scoring: Scoring,
+    pub komi: f32,
+    pub ko: Ko,
+    pub capture_target: u32,
+    pub grid: Grid,
+    pub topology: Topology,
+    pub swap: bool,
+    pub time: TimeControl,
+    pub hash_seed: u64,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            variant: Variant::Atari,
+            full_board: FullBoard::Draw,
+            both_captured: BothCaptured::MoverWins,
+            suicide: Suicide::Allow,
+            scoring: Scoring::Area,
+            komi: 6.5,
+            ko: Ko::Simple,
+            capture_target: 5,
+            grid: Grid::Square,
+            topology: Topology::Plane,
+            swap: false,
+            time: TimeControl::None,
+            hash_seed: zobrist::DEFAULT_SEED,
+        }
+    }
+}
+
+impl Rules {
+    /// the rules in `name=value` form, as stored in
+    /// the save file metadata
+    pub fn options(&self) -> Vec<String> {
+        let variant = match self.variant {
+            Variant::Atari => "atari",
+            Variant::Go => "go",
+            Variant::Capture => "capture",
+        };
+
+        let full_board = match self.full_board {
+            FullBoard::Draw => "draw",
+            FullBoard::PlayerToMoveLoses => "loss",
+        };
+
+        let both_captured = match self.both_captured {
+            BothCaptured::MoverWins => "mover-wins",
+            BothCaptured::MoverLoses => "mover-loses",
+        };
+
+        let suicide = match self.suicide {
+            Suicide::Allow => "allow",
+            Suicide::Forbid => "forbid",
+            Suicide::Warn => "warn",
+        };
+
+        let scoring = match self.scoring {
+            Scoring::Area => "area",
+            Scoring::Territory => "territory",
+        };
+
+        let ko = match self.ko {
+            Ko::None => "none",
+            Ko::Simple => "simple",
+            Ko::Superko => "superko",
+        };
+
+        let grid = match self.grid {
+            Grid::Square => "square",
+            Grid::Hex => "hex",
+        };
+
+        let topology = match self.topology {
+            Topology::Plane => "plane",
+            Topology::Cylinder => "cylinder",
+            Topology::Torus => "torus",
+        };
+
+        vec![format!("variant={}", variant),
+             format!("full-board={}", full_board),
+             format!("both-captured={}", both_captured),
+             format!("suicide={}", suicide),
+             format!("scoring={}", scoring),
+             format!("komi={}", self.komi),
+             format!("ko={}", ko),
+             format!("capture-target={}", self.capture_target),
+             format!("grid={}", grid),
+             format!("topology={}", topology),
+             format!("swap={}", if self.swap { "on" } else { "off" }),
+             format!("time={}", self.time),
+             format!("hash-seed={}", self.hash_seed)]
+    }
+}
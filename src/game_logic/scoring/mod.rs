@@ -0,0 +1,90 @@
+//! This submodule scores a finished game under the full Go
+//! rules.
+//!
+//! An empty region of the board is the territory of a player
+//! if it only borders that player's stones. With area scoring
+//! a player scores their stones on the board plus their
+//! territory, and with territory scoring their territory plus
+//! the stones they have captured. The komi is added to the
+//! score of every player but the first, for moving later.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use super::{NogoBoard, EMPTY};
+use super::rules::Scoring;
+
+
+/// the scores of the players still in the game, in
+/// turn order
+pub fn score(board: &NogoBoard) -> Vec<(char, f32)> {
+    let territories = territory(board);
+
+    board.state
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(_, p)| !p.eliminated)
+        .map(|(i, p)| {
+            let t = territories.get(&p.id()).cloned().unwrap_or(0);
+
+            let s = match board.rules().scoring {
+                Scoring::Area => t + stones(board, p.id()),
+                Scoring::Territory => t + p.captures,
+            };
+
+            let komi = if i == 0 { 0.0 } else { board.rules().komi };
+            (p.id(), s as f32 + komi)
+        })
+        .collect()
+}
+
+/// the number of stones `id` has on the board
+fn stones(board: &NogoBoard, id: char) -> u32 {
+    board.state.cells.iter().filter(|&&t| t == id).count() as u32
+}
+
+/// flood fill every empty region of the board, and
+/// count the regions bordered by only one player
+fn territory(board: &NogoBoard) -> HashMap<char, u32> {
+    let state = &board.state;
+    let mut seen = vec![false; state.cells.len()];
+    let mut territories = HashMap::new();
+
+    for start in 0..state.cells.len() {
+        if seen[start] || state.cells[start] != EMPTY {
+            continue;
+        }
+
+        let (mut size, mut borders) = (0, HashSet::new());
+        let mut queue = VecDeque::new();
+
+        seen[start] = true;
+        queue.push_back(start);
+
+        while let Some(cell) = queue.pop_front() {
+            size += 1;
+            let (x, y) = state.coords(cell);
+
+            for (r, c) in state.neighbours(x, y) {
+                let next = state.index(r, c).unwrap();
+
+                match state.stone_at(r, c) {
+                    None if !seen[next] => {
+                        seen[next] = true;
+                        queue.push_back(next);
+                    }
+                    Some(t) => {
+                        borders.insert(t);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if borders.len() == 1 {
+            let owner = borders.into_iter().next().unwrap();
+            *territories.entry(owner).or_insert(0) += size;
+        }
+    }
+
+    territories
+}
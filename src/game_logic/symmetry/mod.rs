@@ -0,0 +1,123 @@
+//! This submodule finds the symmetries of the board, for
+//! treating positions which differ only by a rotation or a
+//! reflection as the same position.
+//!
+//! A square board has eight symmetries - four rotations and
+//! four reflections - and a rectangular one has the four of
+//! them which keep its height and width. Not all of them fit
+//! every board: a hex grid is only symmetric under the ones
+//! which keep its diagonal neighbours, and a cylinder under
+//! the ones which keep its joined edges at the sides.
+//!
+//! The canonical form of a position is the least of its
+//! images under the symmetries of the board, comparing the
+//! cells row by row, so every position related by a symmetry
+//! has the same canonical form.
+
+use super::Point;
+
+
+/// a rotation or reflection of the board. The rotations
+/// are clockwise, and `Transpose` and `AntiTranspose`
+/// reflect the board in its main and other diagonal
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipUpDown,
+    FlipLeftRight,
+    Transpose,
+    AntiTranspose,
+}
+
+/// every transform, in the order the canonical form
+/// prefers them when several give the same image
+pub const TRANSFORMS: [Transform; 8] = [Transform::Identity,
+                                        Transform::Rotate90,
+                                        Transform::Rotate180,
+                                        Transform::Rotate270,
+                                        Transform::FlipUpDown,
+                                        Transform::FlipLeftRight,
+                                        Transform::Transpose,
+                                        Transform::AntiTranspose];
+
+impl Transform {
+    /// the transform which undoes this one
+    pub fn inverse(&self) -> Transform {
+        match *self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => other,
+        }
+    }
+
+    /// check whether the transform turns rows into
+    /// columns, which only fits a square board
+    pub fn swaps_axes(&self) -> bool {
+        matches!(*self,
+                 Transform::Rotate90 | Transform::Rotate270 | Transform::Transpose |
+                 Transform::AntiTranspose)
+    }
+
+    /// the height and width of a board of the given
+    /// size once transformed
+    pub fn limits(&self, limits: (i32, i32)) -> (i32, i32) {
+        if self.swaps_axes() {
+            (limits.1, limits.0)
+        } else {
+            limits
+        }
+    }
+
+    /// where the point at (x, y) of a board of the given
+    /// size ends up once the board is transformed
+    pub fn apply(&self, p: Point, limits: (i32, i32)) -> Point {
+        let (h, w) = limits;
+
+        let (x, y) = match *self {
+            Transform::Identity => (p.x, p.y),
+            Transform::Rotate90 => (p.y, h - 1 - p.x),
+            Transform::Rotate180 => (h - 1 - p.x, w - 1 - p.y),
+            Transform::Rotate270 => (w - 1 - p.y, p.x),
+            Transform::FlipUpDown => (h - 1 - p.x, p.y),
+            Transform::FlipLeftRight => (p.x, w - 1 - p.y),
+            Transform::Transpose => (p.y, p.x),
+            Transform::AntiTranspose => (w - 1 - p.y, h - 1 - p.x),
+        };
+
+        Point::new(x, y, p.t)
+    }
+
+    /// the step between two cells once transformed,
+    /// given the step before
+    pub fn apply_offset(&self, offset: (i32, i32)) -> (i32, i32) {
+        let (dx, dy) = offset;
+
+        match *self {
+            Transform::Identity => (dx, dy),
+            Transform::Rotate90 => (dy, -dx),
+            Transform::Rotate180 => (-dx, -dy),
+            Transform::Rotate270 => (-dy, dx),
+            Transform::FlipUpDown => (-dx, dy),
+            Transform::FlipLeftRight => (dx, -dy),
+            Transform::Transpose => (dy, dx),
+            Transform::AntiTranspose => (-dy, -dx),
+        }
+    }
+}
+
+
+/// a position in canonical form. `rows` are the rows of
+/// the canonical image, `transform` takes the board to it,
+/// and `key` is the Zobrist hash of the canonical image,
+/// shared by every position related by a symmetry. A point
+/// of the canonical image is taken back to the board by
+/// the inverse of `transform`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalPosition {
+    pub rows: Vec<String>,
+    pub transform: Transform,
+    pub key: u64,
+}
@@ -0,0 +1,39 @@
+//! This submodule holds the shape of the board.
+//!
+//! On the standard plane a cell past the edge of the board
+//! does not exist. A cylinder joins the left and right edges,
+//! so the first and last columns are adjacent, and a torus
+//! joins the top and bottom edges as well.
+
+/// the way the edges of the board are joined
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Topology {
+    Plane,
+    Cylinder,
+    Torus,
+}
+
+impl Topology {
+    /// bring the given coordinates back onto a board of
+    /// the given size across any joined edges, if they
+    /// lie on the board at all
+    pub fn wrap(&self, x: i32, y: i32, limits: (i32, i32)) -> Option<(i32, i32)> {
+        let (h, w) = limits;
+
+        let y = match *self {
+            Topology::Plane => y,
+            Topology::Cylinder | Topology::Torus => y.rem_euclid(w),
+        };
+
+        let x = match *self {
+            Topology::Plane | Topology::Cylinder => x,
+            Topology::Torus => x.rem_euclid(h),
+        };
+
+        if x < 0 || y < 0 || x >= h || y >= w {
+            None
+        } else {
+            Some((x, y))
+        }
+    }
+}
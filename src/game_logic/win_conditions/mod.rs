@@ -0,0 +1,303 @@
+//! This submodule decides when a game is over, and who has won.
+//!
+//! Each way of ending a game is a `WinCondition`. The board is
+//! given one when it is created - by default the one matching
+//! its rules - and asks it for the outcome after every move.
+//! Conditions can be combined with `AnyOf`, so new variants can
+//! be put together without touching the game loop. In a game of
+//! more than two players, a condition may also knock players out
+//! of the game while the rest play on.
+
+use std::fmt;
+use super::{NogoBoard, Outcome, scoring};
+use super::rules::{Rules, Variant, FullBoard, BothCaptured};
+
+
+/// a way for the game to end. Conditions which are
+/// `Clone` can be cloned along with the board they
+/// belong to
+pub trait WinCondition: fmt::Debug + CloneWinCondition {
+    /// the outcome of the game as it stands, if it has
+    /// ended
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome>;
+
+    /// a summary of the finished game to show along with
+    /// the outcome (for instance, the score), if any
+    fn summary(&self, _board: &NogoBoard) -> Option<String> {
+        None
+    }
+
+    /// the players knocked out of the game by the last
+    /// move, when the game goes on without them
+    fn eliminated(&self, _board: &NogoBoard) -> Vec<char> {
+        Vec::new()
+    }
+}
+
+/// cloning of boxed win conditions, which comes with
+/// any condition that is `Clone`
+pub trait CloneWinCondition {
+    fn clone_box(&self) -> Box<dyn WinCondition>;
+}
+
+impl<T> CloneWinCondition for T
+    where T: WinCondition + Clone + 'static
+{
+    fn clone_box(&self) -> Box<dyn WinCondition> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn WinCondition> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// the win condition for the given rules
+pub fn for_rules(rules: &Rules) -> Box<dyn WinCondition> {
+    let no_legal_moves = Box::new(NoLegalMoves { full_board: rules.full_board });
+
+    match rules.variant {
+        Variant::Atari => {
+            Box::new(AnyOf(vec![Box::new(Resignation),
+                                Box::new(FirstCapture { both_captured: rules.both_captured }),
+                                no_legal_moves,
+                                Box::new(AllPassed)]))
+        }
+        Variant::Capture => {
+            Box::new(AnyOf(vec![Box::new(Resignation),
+                                Box::new(CaptureTarget { target: rules.capture_target }),
+                                no_legal_moves,
+                                Box::new(AllPassed)]))
+        }
+        Variant::Go => Box::new(AnyOf(vec![Box::new(Resignation), Box::new(Score)])),
+    }
+}
+
+
+/// the game ends when every player but one has resigned
+/// (or otherwise dropped out), and the one left wins
+#[derive(Debug, Clone)]
+pub struct Resignation;
+
+impl WinCondition for Resignation {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        match board.active_players().as_slice() {
+            &[id] => Some(Outcome::Win(id)),
+            _ => None,
+        }
+    }
+}
+
+
+/// the game ends in a draw once every player has passed
+/// in a row, for the variants which are not scored
+#[derive(Debug, Clone)]
+pub struct AllPassed;
+
+impl WinCondition for AllPassed {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        if (board.passes() as usize) < board.active_players().len() {
+            None
+        } else {
+            Some(Outcome::Draw)
+        }
+    }
+
+    fn summary(&self, board: &NogoBoard) -> Option<String> {
+        self.outcome(board).map(|_| "Every player passed".to_string())
+    }
+}
+
+
+/// Atari Go - a player whose string is captured loses.
+/// With two players the game ends there, and with more
+/// the player drops out until only one is left.
+/// `both_captured` decides a move which leaves strings
+/// of both the mover and other players captured
+#[derive(Debug, Clone)]
+pub struct FirstCapture {
+    pub both_captured: BothCaptured,
+}
+
+impl WinCondition for FirstCapture {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        let captured = self.eliminated(board);
+        if captured.is_empty() {
+            return None;
+        }
+
+        let remaining = board.active_players()
+            .into_iter()
+            .filter(|id| !captured.contains(id))
+            .collect::<Vec<_>>();
+
+        if remaining.len() == 1 {
+            Some(Outcome::Win(remaining[0]))
+        } else {
+            None
+        }
+    }
+
+    fn eliminated(&self, board: &NogoBoard) -> Vec<char> {
+        let mover = match board.last_mover() {
+            Some(mover) => mover,
+            None => return Vec::new(),
+        };
+
+        let others = board.active_players()
+            .into_iter()
+            .filter(|&id| id != mover && board.is_captured(id))
+            .collect::<Vec<_>>();
+
+        match (board.is_captured(mover), others.is_empty()) {
+            (true, false) => {
+                match self.both_captured {
+                    BothCaptured::MoverWins => others,
+                    BothCaptured::MoverLoses => vec![mover],
+                }
+            }
+            (true, true) => vec![mover],
+            (false, _) => others,
+        }
+    }
+}
+
+
+/// the game ends as soon as a player has captured
+/// `target` stones in total
+#[derive(Debug, Clone)]
+pub struct CaptureTarget {
+    pub target: u32,
+}
+
+impl WinCondition for CaptureTarget {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        let mover = board.last_mover()?;
+
+        // a move can only add to the tally of one of the
+        // players - the mover's by capturing, or the next
+        // player's by the mover capturing their own string
+        [mover, board.next_player(mover)]
+            .iter()
+            .find(|&&id| board.captures(id) >= self.target)
+            .map(|&id| Outcome::Win(id))
+    }
+
+    fn summary(&self, board: &NogoBoard) -> Option<String> {
+        let tallies = board.player_ids()
+            .into_iter()
+            .map(|id| format!("Player {} {}", id, board.captures(id)))
+            .collect::<Vec<_>>();
+
+        Some(format!("Captures: {}", tallies.join(", ")))
+    }
+}
+
+
+/// the game ends when the player to move has no legal
+/// moves left, as a draw or a loss for that player
+#[derive(Debug, Clone)]
+pub struct NoLegalMoves {
+    pub full_board: FullBoard,
+}
+
+impl WinCondition for NoLegalMoves {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        let last = board.last_turn()?;
+        let mover = last.player();
+
+        if board.has_legal_moves(board.player_after(last)) {
+            return None;
+        }
+
+        match self.full_board {
+            FullBoard::Draw => Some(Outcome::Draw),
+            // the player to move has nowhere to go, and
+            // so the player who just moved wins
+            FullBoard::PlayerToMoveLoses => Some(Outcome::Win(mover)),
+        }
+    }
+
+    fn summary(&self, board: &NogoBoard) -> Option<String> {
+        let last = board.last_turn()?;
+
+        if board.has_legal_moves(board.player_after(last)) {
+            None
+        } else {
+            Some("No moves left to play".to_string())
+        }
+    }
+}
+
+
+/// full Go - the game ends once every player has passed
+/// in a row, and the player with the highest score wins.
+/// A tie for the highest score is a draw
+#[derive(Debug, Clone)]
+pub struct Score;
+
+impl WinCondition for Score {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        if (board.passes() as usize) < board.active_players().len() {
+            return None;
+        }
+
+        let scores = scoring::score(board);
+        let best = scores.iter().map(|&(_, s)| s).fold(f32::MIN, f32::max);
+        let leaders = scores.iter().filter(|&&(_, s)| s == best).collect::<Vec<_>>();
+
+        if leaders.len() == 1 {
+            Some(Outcome::Win(leaders[0].0))
+        } else {
+            Some(Outcome::Draw)
+        }
+    }
+
+    fn summary(&self, board: &NogoBoard) -> Option<String> {
+        let scores = scoring::score(board)
+            .into_iter()
+            .map(|(id, s)| format!("Player {} {}", id, s))
+            .collect::<Vec<_>>();
+
+        Some(format!("Score: {}", scores.join(", ")))
+    }
+}
+
+
+/// the game ends as soon as any of the conditions is
+/// met, the earlier ones taking precedence
+#[derive(Debug, Clone)]
+pub struct AnyOf(pub Vec<Box<dyn WinCondition>>);
+
+impl WinCondition for AnyOf {
+    fn outcome(&self, board: &NogoBoard) -> Option<Outcome> {
+        self.0.iter().filter_map(|condition| condition.outcome(board)).next()
+    }
+
+    fn summary(&self, board: &NogoBoard) -> Option<String> {
+        let summaries = self.0
+            .iter()
+            .filter_map(|condition| condition.summary(board))
+            .collect::<Vec<_>>();
+
+        if summaries.is_empty() {
+            None
+        } else {
+            Some(summaries.join("\n"))
+        }
+    }
+
+    fn eliminated(&self, board: &NogoBoard) -> Vec<char> {
+        let mut eliminated = Vec::new();
+
+        for id in self.0.iter().flat_map(|condition| condition.eliminated(board)) {
+            if !eliminated.contains(&id) {
+                eliminated.push(id);
+            }
+        }
+
+        eliminated
+    }
+}
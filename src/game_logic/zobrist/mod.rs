@@ -0,0 +1,57 @@
+//! This submodule hashes board positions, for telling
+//! positions apart cheaply.
+//!
+//! Each piece a cell can hold (a player's stone, or a blocked
+//! cell) has a random key for that cell, and the hash of a
+//! position is all the keys of its cells XORed together. An
+//! empty cell adds nothing. Changing a cell then only takes
+//! two XORs, so the board keeps its hash up to date as it
+//! goes. The keys are worked out from a seed rather than kept
+//! in a table - a board can have up to a million cells - and
+//! the same seed always gives the same keys, from one run to
+//! the next.
+
+use super::EMPTY;
+
+/// the seed boards are hashed with unless the rules of
+/// the game give another
+pub const DEFAULT_SEED: u64 = 0x6e6f_676f_2d7a_6f62;
+
+/// the keys for hashing positions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Zobrist {
+    seed: u64,
+}
+
+impl Zobrist {
+    pub fn new(seed: u64) -> Self {
+        Zobrist { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// the key for `t` on the cell, or 0 for an empty cell
+    pub fn key(&self, cell: usize, t: char) -> u64 {
+        if t == EMPTY {
+            return 0;
+        }
+
+        mix(mix(self.seed ^ cell as u64) ^ t as u64)
+    }
+
+    /// the hash of a whole board, given its cells
+    pub fn hash<I: Iterator<Item = char>>(&self, cells: I) -> u64 {
+        cells.enumerate().fold(0, |hash, (cell, t)| hash ^ self.key(cell, t))
+    }
+}
+
+/// the SplitMix64 finaliser, which spreads every bit of its
+/// input over the whole of its output
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
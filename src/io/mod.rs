@@ -1,11 +1,15 @@
-/// All the I/O and visual rendering of the game
-/// is handled by this module
+//! All the I/O and visual rendering of the game
+//! is handled by this module
 
 use std::io::{self, Write, BufWriter, BufRead, BufReader};
 use std::fs::File;
 use std::str::FromStr;
+use std::time::Duration;
 
 use game_logic as gl;
+use game_logic::geometry::Grid;
+use game_logic::clock::Clock;
+use game_logic::rules::{Rules, Suicide, TimeControl};
 use error_handling as eh;
 
 
@@ -20,43 +24,141 @@ pub fn get_game_arguments() -> Vec<String> {
 /// display the correct usage of
 /// the game
 pub fn display_usage() {
-    writeln!(io::stderr(),
-             "Usage: nogo p1type p2type [height width | filename]")
-        .unwrap();
+    eprintln!("Usage: nogo [p1type p2type height width [--rule=value ...] | filename]");
     eh::exit_with_error(eh::construct_error("insufficient number of arguments",
                                             eh::NogoErrorKind::IncorrectNumberOfArgs));
 }
 
 
+/// display the current state of the board
+pub fn display_board(board: &gl::NogoBoard) {
+    match board.rules().grid {
+        Grid::Square => {
+            print_head(board.width());
+            print_rows(board);
+            print_tail(board.width());
+        }
+
+        Grid::Hex => print_hex_board(board),
+    }
+}
+
+fn print_head(n: i32) {
+    print!("/");
+
+    for _ in 0..n {
+        print!("-");
+    }
+    println!("\\");
+}
+
+/// print the board row by row, one character per
+/// cell
+fn print_rows(board: &gl::NogoBoard) {
+    for row in (0..board.height()).filter_map(|i| board.row(i)) {
+        println!("|{}|", row);
+    }
+}
+
+fn print_tail(n: i32) {
+    print!("\\");
+
+    for _ in 0..n {
+        print!("-");
+    }
+    println!("/\n");
+}
+
+/// display a hex board as a parallelogram, with
+/// each row shifted half a cell to the right of
+/// the row above, so that every cell sits between
+/// its neighbours in the rows above and below
+fn print_hex_board(board: &gl::NogoBoard) {
+    let border = "-".repeat(2 * board.width() as usize + 1);
+    println!(" {}", border);
+
+    for (i, row) in (0..board.height()).filter_map(|i| board.row(i)).enumerate() {
+        let cells = row.chars()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+
+        println!("{}\\ {} \\", " ".repeat(i), cells.join(" "));
+    }
+
+    println!("{} {}\n", " ".repeat(board.height() as usize), border);
+}
+
+
 /// The player can enter either a (row, column) pair, or
-/// w[full-path-to-save-file]. Handle either situation
-/// with proper validation
-pub fn get_player_move(board: &gl::NogoBoard, player_name: char) -> gl::PlayerInput {
+/// w[full-path-to-save-file], or `pass` or `resign`, or
+/// `swap` when the swap rule allows it, or
+/// `undo` and `redo` to go back and forth through the
+/// moves played. Handle each situation with proper
+/// validation. The prompt shows the time the player has
+/// left in a timed game
+pub fn get_player_move(game: &gl::Game) -> gl::PlayerInput {
+    let board = game.board();
+    let player_name = game.current_player();
     let mut r;
     let mut c;
 
     loop {
-        print!("Player {}> ", player_name);
+        match game.clock(player_name) {
+            Some(clock) => print!("Player {} ({})> ", player_name, clock),
+            None => print!("Player {}> ", player_name),
+        }
         io::stdout().flush().unwrap();
 
-        let mut input = String::new();
+        let input = match read_input_line() {
+            Some(line) => line,
+            None => continue,
+        };
+
+        let entries = input
+            .split_whitespace()
+            .collect::<Vec<_>>();
 
-        if io::stdin().read_line(&mut input).is_err() {
+        if entries.is_empty() {
             continue;
         }
 
-        let entries = input.trim()
-            .split_whitespace()
-            .collect::<Vec<_>>();
+        if entries.len() == 1 && entries[0] == "pass" {
+            return gl::PlayerInput::Pass;
+        }
+
+        if entries.len() == 1 && entries[0] == "resign" {
+            return gl::PlayerInput::Resign;
+        }
+
+        if entries.len() == 1 && entries[0] == "swap" {
+            if board.can_swap(player_name) {
+                return gl::PlayerInput::Swap;
+            }
+            continue;
+        }
+
+        if entries.len() == 1 && entries[0] == "undo" {
+            if board.can_undo() {
+                return gl::PlayerInput::Undo;
+            }
+            continue;
+        }
+
+        if entries.len() == 1 && entries[0] == "redo" {
+            if board.can_redo() {
+                return gl::PlayerInput::Redo;
+            }
+            continue;
+        }
 
         // check if the user wants to save the game
         if entries.len() == 1 {
-            match entries[0].trim().chars().nth(0) {
+            match entries[0].trim().chars().next() {
                 Some('w') | Some('W') => {
                     let path =
                         String::from_utf8(entries[0].trim().bytes().skip(1).collect::<Vec<_>>())
                             .unwrap();
-                    if path.len() != 0 {
+                    if !path.is_empty() {
                         return gl::PlayerInput::Save(path);
                     }
                 }
@@ -89,7 +191,12 @@ pub fn get_player_move(board: &gl::NogoBoard, player_name: char) -> gl::PlayerIn
         }
 
         // game-logic related validation here
-        if !eh::validation::validate_user_move(board, (r, c)) {
+        if !eh::validation::validate_user_move(board, (r, c), player_name) {
+            continue;
+        }
+
+        if board.rules().suicide == Suicide::Warn && board.is_self_capture(r, c, player_name) &&
+           !confirm_self_capture(player_name) {
             continue;
         }
 
@@ -97,6 +204,41 @@ pub fn get_player_move(board: &gl::NogoBoard, player_name: char) -> gl::PlayerIn
     } // loop
 }
 
+/// warn the player that the move captures their own
+/// string, and ask whether to play it anyway
+fn confirm_self_capture(player_name: char) -> bool {
+    loop {
+        print!("Player {}: this move captures your own string and loses the game. Play it anyway? \
+                (y/n) ",
+               player_name);
+        io::stdout().flush().unwrap();
+
+        if let Some(line) = read_input_line() {
+            match line.trim() {
+                "y" | "Y" => return true,
+                "n" | "N" => return false,
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// read a line of input from the player, exiting
+/// the game when there is no more input to read
+fn read_input_line() -> Option<String> {
+    let mut input = String::new();
+
+    match io::stdin().read_line(&mut input) {
+        Ok(0) => {
+            eh::exit_with_error(eh::construct_error("no more input to read",
+                                                    eh::NogoErrorKind::EOFWaitingForUserInput));
+            None
+        }
+        Ok(_) => Some(input),
+        Err(_) => None,
+    }
+}
+
 
 /// save the game to the given save file
 pub fn save_game_state<'a>(path: &str, data: Vec<String>) -> eh::Result<'a, ()> {
@@ -125,16 +267,290 @@ pub fn load_game_state<'a>(path: &str) -> eh::Result<'a, Vec<String>> {
     Ok(data)
 }
 
+/// load the rows of a board mask or setup, one row
+/// per line, skipping any blank lines
+pub fn load_board_rows<'a>(path: &str) -> eh::Result<'a, Vec<String>> {
+    let mut rows = Vec::new();
+    let reader = BufReader::new(File::open(path)?);
+
+    for line in reader.lines() {
+        let line = line?;
+
+        if !line.trim().is_empty() {
+            rows.push(line.trim().to_string());
+        }
+    }
+
+    Ok(rows)
+}
+
+/// the game metadata held in the first line of a
+/// save file. `clocks` are the clocks of the players
+/// in turn order, for a timed game
+#[derive(Debug)]
+pub struct SaveMetadata {
+    pub height: i32,
+    pub width: i32,
+    pub players: gl::Players,
+    pub curr_player: char,
+    pub rules: Rules,
+    pub progress: Progress,
+    pub clocks: Vec<Clock>,
+}
+
+/// how far a game has got - the number of passes in a
+/// row and of turns played, the tally of captures of
+/// each player in turn order, and the players who are
+/// out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub passes: u32,
+    pub moves: u32,
+    pub captures: Vec<u32>,
+    pub eliminated: Vec<char>,
+}
+
+impl Progress {
+    /// the progress of a fresh game of `players` players
+    pub fn new(players: usize) -> Self {
+        Progress {
+            passes: 0,
+            moves: 0,
+            captures: vec![0; players],
+            eliminated: Vec::new(),
+        }
+    }
+}
+
+/// the part of a save file after the board - the position
+/// the turns were played from, with the players and the
+/// progress of the game at that point and its stones, and
+/// the turns played since, each with the players it
+/// knocked out. Older save files hold only the board, and
+/// some leave out the players the turns began with
+#[derive(Debug)]
+pub struct SaveHistory {
+    pub players: Option<gl::Players>,
+    pub progress: Progress,
+    pub stones: Vec<gl::Point>,
+    pub turns: Vec<(gl::Move, Vec<char>)>,
+}
+
 /// parse the saved file metadata to reconstruct the game
-/// state
-pub fn parse_save_file_metadata<'a>
-    (metadata: &Vec<&str>)
-     -> eh::Result<'a, (i32, i32, gl::PlayerType, gl::PlayerType, char)> {
-    Ok((i32::from_str(&metadata[0])?,
-        i32::from_str(&metadata[1])?,
-        get_player_type(&metadata[2])?,
-        get_player_type(&metadata[3])?,
-        metadata[4].chars().nth(0).unwrap()))
+/// state. The metadata starts with the board dimensions and
+/// the player to move, followed by a `players` entry. Older
+/// save files instead give the types of the `0` and `X`
+/// players before the player to move. Any other entries are
+/// either the progress of the game (`passes`, `moves`,
+/// `captures` and `eliminated`), the time left on the
+/// clocks of a timed game (`clocks`), or the rules the
+/// game is played under
+pub fn parse_save_file_metadata<'a>(metadata: &[&str]) -> eh::Result<'a, SaveMetadata> {
+    let player_list = metadata.iter().find(|entry| entry.starts_with("players="));
+
+    let (players, curr_player, rest) = match player_list {
+        Some(list) if metadata.len() >= 4 => {
+            let players =
+                eh::validation::validate_player_list(list.trim_start_matches("players="))?;
+            (players, metadata[2], &metadata[3..])
+        }
+        None if metadata.len() >= 5 => {
+            let players = vec![(gl::PLAYER_ZERO, get_player_type(metadata[2])?),
+                               (gl::PLAYER_ONE, get_player_type(metadata[3])?)];
+            (players, metadata[4], &metadata[5..])
+        }
+        _ => {
+            return Err(eh::construct_error("incomplete save file metadata",
+                                           eh::NogoErrorKind::ErrorReadingGameFile));
+        }
+    };
+
+    let mut progress = Progress::new(players.len());
+    let mut options = Vec::new();
+    let mut clocks = None;
+
+    for entry in rest.iter() {
+        if entry.starts_with("clocks=") {
+            clocks = Some(entry.trim_start_matches("clocks="));
+        } else if !entry.starts_with("players=") && !parse_progress_entry(entry, &mut progress)? {
+            options.push(*entry);
+        }
+    }
+
+    let (height, width) = eh::validation::validate_board_dimensions(metadata[0], metadata[1])?;
+    let rules = eh::validation::validate_rule_options(&options)?;
+    let clocks = match clocks {
+        Some(clocks) => parse_clocks(clocks, rules.time, players.len())?,
+        None => Vec::new(),
+    };
+
+    let curr_player = match curr_player.chars().next() {
+        Some(id) if players.iter().any(|&(other, _)| other == id) &&
+                    !progress.eliminated.contains(&id) => id,
+        _ => {
+            return Err(eh::construct_error("incorrect player to move in save file metadata",
+                                           eh::NogoErrorKind::ErrorReadingGameFile));
+        }
+    };
+
+    Ok(SaveMetadata {
+        height,
+        width,
+        players,
+        curr_player,
+        rules,
+        progress,
+        clocks,
+    })
+}
+
+/// read the `clocks` entry of the metadata - the time
+/// left on the clock of each player in turn order, in
+/// milliseconds, followed by `/periods` for the byo-yomi
+/// periods left after the current one
+fn parse_clocks<'a>(value: &str, time: TimeControl, players: usize) -> eh::Result<'a, Vec<Clock>> {
+    let clocks = value.split(',').collect::<Vec<_>>();
+    if clocks.len() != players {
+        return Err(eh::construct_error("incorrect clocks in save file metadata",
+                                       eh::NogoErrorKind::ErrorReadingGameFile));
+    }
+
+    let mut restored = Vec::new();
+    for clock in clocks {
+        let mut parts = clock.splitn(2, '/');
+        let remaining = u64::from_str(parts.next().unwrap_or(""))?;
+        let periods = match parts.next() {
+            Some(periods) => u32::from_str(periods)?,
+            None => 0,
+        };
+
+        restored.push(Clock::restore(time, Duration::from_millis(remaining), periods));
+    }
+
+    Ok(restored)
+}
+
+/// read a `passes`, `moves`, `captures` or `eliminated`
+/// entry into the progress of the game, and return
+/// whether the entry was one of them
+fn parse_progress_entry<'a>(entry: &str, progress: &mut Progress) -> eh::Result<'a, bool> {
+    let mut parts = entry.splitn(2, '=');
+
+    match (parts.next(), parts.next()) {
+        (Some("passes"), Some(value)) => progress.passes = u32::from_str(value)?,
+
+        (Some("moves"), Some(value)) => progress.moves = u32::from_str(value)?,
+
+        (Some("captures"), Some(value)) => {
+            let counts = value.split(',').collect::<Vec<_>>();
+            if counts.len() != progress.captures.len() {
+                return Err(eh::construct_error("incorrect captures in save file metadata",
+                                               eh::NogoErrorKind::ErrorReadingGameFile));
+            }
+            for (tally, count) in progress.captures.iter_mut().zip(counts) {
+                *tally = u32::from_str(count)?;
+            }
+        }
+
+        (Some("eliminated"), Some(value)) => {
+            progress.eliminated = value.split(',').filter_map(|id| id.chars().next()).collect();
+        }
+
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// parse the part of a save file after the board. It
+/// starts with a `start` line giving the players and the
+/// progress of the game when the turns began, followed by
+/// the board at that point, and ends with a `history`
+/// line giving the turns in order - `id:r,c` for a stone,
+/// `id:pass`, `id:resign` and `id:swap`, with `id:out`
+/// after a turn for each player the turn knocked out
+pub fn parse_save_file_history<'a>(data: &[&String],
+                                   ids: &[char],
+                                   height: i32,
+                                   width: i32)
+                                   -> eh::Result<'a, SaveHistory> {
+    let incomplete = || {
+        eh::construct_error("incomplete move history in save file",
+                            eh::NogoErrorKind::ErrorReadingGameFile)
+    };
+
+    let (start, rest) = data.split_first().ok_or_else(incomplete)?;
+    let (history, rows) = rest.split_last().ok_or_else(incomplete)?;
+
+    let invalid_entry = || {
+        eh::construct_error("invalid entry in save file history",
+                            eh::NogoErrorKind::ErrorReadingGameFile)
+    };
+
+    let mut players = None;
+    let mut progress = Progress::new(ids.len());
+    for entry in start.split_whitespace().skip(1) {
+        if entry.starts_with("players=") {
+            let list =
+                eh::validation::validate_player_list(entry.trim_start_matches("players="))?;
+
+            if !list.iter().map(|&(id, _)| id).eq(ids.iter().cloned()) {
+                return Err(invalid_entry());
+            }
+
+            players = Some(list);
+        } else if !parse_progress_entry(entry, &mut progress)? {
+            return Err(invalid_entry());
+        }
+    }
+
+    check_board_rows(rows, height, width)?;
+    let (stones, _) = parse_player_strings_from_saved_file(rows, ids)?;
+
+    let mut entries = history.split_whitespace();
+    if entries.next() != Some("history") {
+        return Err(incomplete());
+    }
+
+    let mut turns: Vec<(gl::Move, Vec<char>)> = Vec::new();
+    for entry in entries {
+        let invalid = || {
+            eh::construct_error("invalid turn in save file history",
+                                eh::NogoErrorKind::ErrorReadingGameFile)
+        };
+
+        let mut parts = entry.splitn(2, ':');
+        let (id, turn) = match (parts.next(), parts.next()) {
+            (Some(id), Some(turn)) if id.chars().count() == 1 => (id.chars().next().unwrap(), turn),
+            _ => return Err(invalid()),
+        };
+
+        if !ids.contains(&id) {
+            return Err(invalid());
+        }
+
+        match turn {
+            "out" => turns.last_mut().ok_or_else(invalid)?.1.push(id),
+            "pass" => turns.push((gl::Move::Pass(id), Vec::new())),
+            "resign" => turns.push((gl::Move::Resign(id), Vec::new())),
+            "swap" => turns.push((gl::Move::Swap(id), Vec::new())),
+            _ => {
+                let mut coords = turn.splitn(2, ',');
+                let (r, c) = match (coords.next(), coords.next()) {
+                    (Some(r), Some(c)) => (i32::from_str(r)?, i32::from_str(c)?),
+                    _ => return Err(invalid()),
+                };
+                turns.push((gl::Move::Stone(gl::Point::new(r, c, id)), Vec::new()));
+            }
+        }
+    }
+
+    Ok(SaveHistory {
+        players,
+        progress,
+        stones,
+        turns,
+    })
 }
 
 fn get_player_type<'a>(p: &str) -> eh::Result<'a, gl::PlayerType> {
@@ -142,27 +558,46 @@ fn get_player_type<'a>(p: &str) -> eh::Result<'a, gl::PlayerType> {
         "c" | "C" => Ok(gl::PlayerType::COMPUTER),
         "h" | "H" => Ok(gl::PlayerType::HUMAN),
         _ => {
-            return Err(eh::construct_error("incorrect type for player 0",
-                                           eh::NogoErrorKind::IncorrectTypes));
+            Err(eh::construct_error("incorrect type for player 0",
+                                    eh::NogoErrorKind::IncorrectTypes))
         }
     }
 }
 
-/// parse the rest of the save file to generate
-/// a pair of points for both players
-pub fn parse_player_strings_from_saved_file<'a>
-    (data: &Vec<&String>)
-     -> eh::Result<'a, (Vec<gl::Point>, Vec<gl::Point>)> {
+/// check that the rows of a saved board have the size
+/// given in the metadata - a row for each row of the
+/// board, and a cell for each column
+pub fn check_board_rows<'a>(rows: &[&String], height: i32, width: i32) -> eh::Result<'a, ()> {
+    if rows.len() == height as usize && rows.iter().all(|row| row.chars().count() == width as usize) {
+        Ok(())
+    } else {
+        Err(eh::construct_error("saved board does not match the board size",
+                                eh::NogoErrorKind::ErrorReadingGameFile))
+    }
+}
 
-    let (mut zero_points, mut x_points) = (Vec::new(), Vec::new());
+/// parse the rest of the save file to generate
+/// the points of all the players, along with the
+/// blocked cells of the board. `ids` are the pieces
+/// of the players - as in older save files, a lower
+/// case `x` is also read as the `X` player
+pub fn parse_player_strings_from_saved_file<'a>(data: &[&String],
+                                                ids: &[char])
+                                                -> eh::Result<'a, (Vec<gl::Point>, gl::Mask)> {
+
+    let (mut points, mut blocked) = (Vec::new(), Vec::new());
     let (mut i, mut j) = (0, 0);
 
     for line in data.iter() {
         for c in line.chars() {
             match c {
-                '0' => zero_points.push(gl::Point::new(i, j, gl::PLAYER_ZERO)),
+                _ if ids.contains(&c) => points.push(gl::Point::new(i, j, c)),
+
+                'x' if ids.contains(&gl::PLAYER_ONE) => {
+                    points.push(gl::Point::new(i, j, gl::PLAYER_ONE))
+                }
 
-                'x' | 'X' => x_points.push(gl::Point::new(i, j, gl::PLAYER_ONE)),
+                '#' => blocked.push((i, j)),
 
                 '.' => {}
 
@@ -177,5 +612,5 @@ pub fn parse_player_strings_from_saved_file<'a>
         j = 0;
     }
 
-    Ok((zero_points, x_points))
+    Ok((points, blocked))
 }
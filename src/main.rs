@@ -1,32 +1,232 @@
 extern crate nogo;
 
 use nogo::io;
-use nogo::game_logic;
+use nogo::error_handling as eh;
+use nogo::game_logic::{Game, Move, MoveOutcome, Outcome, PlayerInput, PlayerType, Point};
 
 
 /// Game entry point
 fn main() {
-    let args = io::get_game_arguments();
+    let (args, options): (Vec<String>, Vec<String>) = io::get_game_arguments()
+        .into_iter()
+        .partition(|arg| !arg.starts_with("--"));
 
-    match args.len() {
-        1 => continue_saved_game(&args[0]),
-        4 => start_new_game(args),
+    match (args.len(), options.len()) {
+        (1, 0) => continue_saved_game(&args[0]),
+        (4, _) => start_new_game(args, options),
         _ => io::display_usage(),
     }
 }
 
 /// start a fresh game with the
-/// given specifications with relevant
-/// validation done
-fn start_new_game(args: Vec<String>) {
+/// given specifications and rule
+/// options with relevant validation
+/// done
+fn start_new_game(args: Vec<String>, options: Vec<String>) {
     println!("Welcome to nogo!\n");
-    game_logic::start_new_game(&args[0], &args[1], &args[2], &args[3]);
+
+    // check if the arguments are correct
+    let game = eh::validation::validate_new_game_parameters(&args[0],
+                                                            &args[1],
+                                                            &args[2],
+                                                            &args[3],
+                                                            &options)
+        .and_then(Game::new);
+
+    match game {
+        Ok(game) => game_loop(game),
+        Err(e) => eh::exit_with_error(e),
+    }
 }
 
 
 /// continue the saved game as given
-/// in the save file
+/// in the save file, with the player who
+/// had saved the file getting the first
+/// move
 fn continue_saved_game(file: &str) {
     println!("Welcome to nogo again!\n");
-    game_logic::continue_saved_game(file);
+
+    match io::load_game_state(file).and_then(|data| Game::restore(&data)) {
+        Ok(game) => game_loop(game),
+        Err(e) => eh::exit_with_error(e),
+    }
+}
+
+
+/// the players take turns in order, starting with
+/// the player to move when the game begins. When a
+/// player takes turns back or makes them again, the
+/// turn goes to whoever is to move at that point
+fn game_loop(mut game: Game) {
+    let start_player = game.current_player();
+
+    // a game saved once it was over has no turns left
+    if let Some(outcome) = game.outcome() {
+        end_game(&game, outcome);
+    }
+
+    game.start();
+    io::display_board(game.board());
+
+    loop {
+        let player = game.current_player();
+
+        let mov = match next_move(&mut game) {
+            Some(mov) => mov,
+            None => {
+                io::display_board(game.board());
+                continue;
+            }
+        };
+
+        announce(&game, mov);
+
+        let result = match game.play(mov) {
+            Ok(result) => result,
+            Err(e) => return eh::exit_with_error(e),
+        };
+
+        // after a swap the same player goes on to play
+        // a stone with its new pieces
+        if let Move::Swap(_) = mov {
+            continue;
+        }
+
+        if player == start_player {
+            io::display_board(game.board());
+            report(&game, &result);
+        } else {
+            report(&game, &result);
+            io::display_board(game.board());
+        }
+    }
+} // game loop
+
+
+/// the next move of the player to move - a human enters
+/// it, and the computer generates it. Taking turns back,
+/// making them again and saving the game are dealt with
+/// here, and leave no move to play
+fn next_move(game: &mut Game) -> Option<Move> {
+    let p_id = game.current_player();
+
+    if game.board().player_type(p_id) != Some(PlayerType::HUMAN) {
+        return Some(game.computer_move());
+    }
+
+    match io::get_player_move(game) {
+        PlayerInput::Point(x, y) => Some(Move::Stone(Point::new(x, y, p_id))),
+        PlayerInput::Pass => Some(Move::Pass(p_id)),
+        PlayerInput::Resign => Some(Move::Resign(p_id)),
+        PlayerInput::Swap => Some(Move::Swap(p_id)),
+        PlayerInput::Undo => {
+            undo_moves(game);
+            None
+        }
+        PlayerInput::Redo => {
+            redo_moves(game);
+            None
+        }
+        PlayerInput::Save(path) => {
+            save_game_and_exit(&path, game);
+            None
+        }
+    }
+}
+
+
+/// let the players know about the move about to be
+/// played, unless a human has just entered it
+fn announce(game: &Game, mov: Move) {
+    match mov {
+        Move::Stone(p) => {
+            if game.board().player_type(*p.t()) != Some(PlayerType::HUMAN) {
+                println!("Player {}: {} {}", p.t(), p.x(), p.y());
+            }
+        }
+        Move::Pass(id) => println!("Player {} passes\n", id),
+        Move::Resign(id) => println!("Player {} resigns\n", id),
+        Move::Swap(id) => println!("Player {} swaps\n", id),
+    }
+}
+
+
+/// take back turns until one made by a human has been
+/// taken back (or there are none left), so that the
+/// computer does not simply play its turns again
+fn undo_moves(game: &mut Game) {
+    println!("Player {} takes back a turn\n", game.current_player());
+
+    while let Some(mov) = game.undo() {
+        if game.board().player_type(mov.player()) == Some(PlayerType::HUMAN) {
+            break;
+        }
+    }
+}
+
+
+/// make a turn taken back again, along with any turns
+/// of the computer which follow it
+fn redo_moves(game: &mut Game) {
+    println!("Player {} replays a turn\n", game.current_player());
+
+    while game.redo().is_some() {
+        let computer_next = game.board().player_type(game.current_player()) ==
+                            Some(PlayerType::COMPUTER);
+
+        if !computer_next || !game.board().can_redo() {
+            break;
+        }
+    }
+}
+
+
+/// Save the game - first retrieve the current game state in proper form
+/// and then save it to the save file. Then quite the game gracefully
+fn save_game_and_exit(save_file: &str, game: &mut Game) {
+    match game.save(save_file) {
+        Ok(_) => {
+            println!("\nFinished saving current game state to file {}", save_file);
+            eh::clean_exit();
+        }
+        Err(e) => eh::exit_with_error(e),
+    }
+}
+
+
+/// if the move has ended the game, announce the
+/// outcome and end the game. Otherwise let the players
+/// know who the move knocked out, and the rest play on.
+/// A player who ran out of time is announced first
+fn report(game: &Game, result: &MoveOutcome) {
+    if let Some(id) = result.out_of_time {
+        println!("Player {} runs out of time\n", id);
+    }
+
+    if let Some(outcome) = result.outcome {
+        end_game(game, outcome);
+    }
+
+    for id in result.eliminated.iter() {
+        println!("Player {} is out!\n", id);
+    }
+}
+
+
+/// show the finished board and announce the outcome,
+/// then end the game
+fn end_game(game: &Game, outcome: Outcome) {
+    io::display_board(game.board());
+
+    if let Some(summary) = game.summary() {
+        println!("{}\n", summary);
+    }
+
+    match outcome {
+        Outcome::Win(id) => println!("Player {} wins!", id),
+        Outcome::Draw => println!("The game is a draw!"),
+    }
+
+    eh::clean_exit_with_status(outcome.status());
 }
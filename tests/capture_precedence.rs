@@ -0,0 +1,112 @@
+//! A corpus of positions for the rule deciding who wins when a
+//! move leaves strings of both players without liberties.
+//!
+//! Each position is given in the save file format, followed by
+//! the move to play and the expected outcome under both the
+//! `mover-wins` and the `mover-loses` rules.
+
+extern crate nogo;
+
+use nogo::game_logic::{self as gl, Outcome, Point};
+
+struct Position {
+    name: &'static str,
+    rows: &'static [&'static str],
+    mover: char,
+    mv: (i32, i32),
+    mover_wins: Option<Outcome>,
+    mover_loses: Option<Outcome>,
+}
+
+const CORPUS: &[Position] = &[
+    // 0 fills the last liberty of the X stone at (0, 1),
+    // but has no liberty itself
+    Position {
+        name: "both captured, 0 to move",
+        rows: &[".X0..", "X0...", ".....", ".....", "....."],
+        mover: '0',
+        mv: (0, 0),
+        mover_wins: Some(Outcome::Win('0')),
+        mover_loses: Some(Outcome::Win('X')),
+    },
+    // the same position with the colours swapped
+    Position {
+        name: "both captured, X to move",
+        rows: &[".0X..", "0X...", ".....", ".....", "....."],
+        mover: 'X',
+        mv: (0, 0),
+        mover_wins: Some(Outcome::Win('X')),
+        mover_loses: Some(Outcome::Win('0')),
+    },
+    // a bridging move which joins two 0 strings into one
+    // with no liberty, while capturing the X string
+    Position {
+        name: "both captured by a bridging move",
+        rows: &["0.0X.", "XXX0.", "000..", ".....", "....."],
+        mover: '0',
+        mv: (0, 1),
+        mover_wins: Some(Outcome::Win('0')),
+        mover_loses: Some(Outcome::Win('X')),
+    },
+    Position {
+        name: "only the opponent captured",
+        rows: &["X0...", ".....", ".....", ".....", "....."],
+        mover: '0',
+        mv: (1, 0),
+        mover_wins: Some(Outcome::Win('0')),
+        mover_loses: Some(Outcome::Win('0')),
+    },
+    Position {
+        name: "only the mover captured",
+        rows: &[".0...", "0....", ".....", ".....", "....."],
+        mover: 'X',
+        mv: (0, 0),
+        mover_wins: Some(Outcome::Win('0')),
+        mover_loses: Some(Outcome::Win('0')),
+    },
+    Position {
+        name: "nothing captured",
+        rows: &[".X0..", "X0...", ".....", ".....", "....."],
+        mover: 'X',
+        mv: (2, 2),
+        mover_wins: None,
+        mover_loses: None,
+    },
+];
+
+fn outcome_after_move(position: &Position, rule: &str) -> Option<Outcome> {
+    let mut data = vec![format!("5 5 h h {} both-captured={}", position.mover, rule)];
+    data.extend(position.rows.iter().map(|row| row.to_string()));
+
+    let (mut board, curr_player) = match gl::restore_board(&data) {
+        Ok(restored) => restored,
+        Err(e) => panic!("{}: could not restore the position - {}", position.name, e),
+    };
+
+    assert_eq!(curr_player, position.mover, "{}", position.name);
+    assert!(gl::game_outcome(&board).is_none(), "{}: game already over", position.name);
+    assert!(board.is_free(position.mv.0, position.mv.1), "{}", position.name);
+
+    board.update_occupied(Point::new(position.mv.0, position.mv.1, position.mover));
+    gl::game_outcome(&board)
+}
+
+#[test]
+fn mover_wins_when_both_sides_are_captured() {
+    for position in CORPUS {
+        assert_eq!(outcome_after_move(position, "mover-wins"),
+                   position.mover_wins,
+                   "{}",
+                   position.name);
+    }
+}
+
+#[test]
+fn self_capture_loses_when_both_sides_are_captured() {
+    for position in CORPUS {
+        assert_eq!(outcome_after_move(position, "mover-loses"),
+                   position.mover_loses,
+                   "{}",
+                   position.name);
+    }
+}
@@ -0,0 +1,129 @@
+//! The ko rules forbid a move which would take the board back
+//! to an earlier position.
+//!
+//! Most tests start from the same ko shape in a `go` game. X
+//! takes the 0 stone at (1, 1) by playing at (1, 2), and 0
+//! taking the X stone straight back would bring back the
+//! position from before X's move.
+
+extern crate nogo;
+
+use nogo::game_logic::{Game, Move, Point};
+
+const KO_SHAPE: &[&str] = &[".X0..", "X0.0.", ".X0..", ".....", "....."];
+
+/// three kos side by side - X holds the first and the
+/// last, and 0 the one in the middle
+const TRIPLE_KO: &[&str] = &[".X0...X0...X0.",
+                             "X.X0.X0.0.X.X0",
+                             ".X0...X0...X0.",
+                             ".............."];
+
+/// the position given by `rows` with `mover` to move, in a
+/// `go` game under the given ko rule
+fn go_game(rows: &[&str], mover: char, ko: &str) -> Game {
+    let mut data = vec![format!("{} {} {} players=0:h,X:h variant=go ko={}",
+                                rows.len(),
+                                rows[0].len(),
+                                mover,
+                                ko)];
+    data.extend(rows.iter().map(|row| row.to_string()));
+
+    Game::restore(&data).unwrap()
+}
+
+/// the ko shape with X to move, under the given ko rule
+fn ko_game(ko: &str) -> Game {
+    go_game(KO_SHAPE, 'X', ko)
+}
+
+fn play(game: &mut Game, x: i32, y: i32) {
+    let id = game.current_player();
+    game.play(Move::Stone(Point::new(x, y, id))).unwrap();
+}
+
+/// X takes the ko, leaving 0 to move
+fn take_ko(ko: &str) -> Game {
+    let mut game = ko_game(ko);
+    play(&mut game, 1, 2);
+
+    assert_eq!(game.board().stone_at(1, 1), None);
+    assert_eq!(game.current_player(), '0');
+    game
+}
+
+#[test]
+fn simple_ko_forbids_taking_the_ko_straight_back() {
+    let mut game = take_ko("simple");
+
+    assert!(!game.board().is_legal(1, 1, '0'));
+    assert!(game.play(Move::Stone(Point::new(1, 1, '0'))).is_err());
+}
+
+#[test]
+fn superko_forbids_taking_the_ko_straight_back() {
+    let game = take_ko("superko");
+
+    assert!(!game.board().is_legal(1, 1, '0'));
+}
+
+#[test]
+fn the_ko_can_be_taken_back_after_a_move_elsewhere() {
+    for ko in ["simple", "superko"].iter() {
+        let mut game = take_ko(ko);
+
+        // a ko threat, and the answer to it, change the
+        // board, so taking the ko back no longer repeats
+        // an earlier position
+        play(&mut game, 4, 4);
+        play(&mut game, 4, 0);
+
+        assert!(game.board().is_legal(1, 1, '0'), "ko={}", ko);
+    }
+}
+
+#[test]
+fn without_a_ko_rule_the_ko_can_be_taken_straight_back() {
+    let mut game = take_ko("none");
+
+    assert!(game.board().is_legal(1, 1, '0'));
+    play(&mut game, 1, 1);
+
+    assert_eq!(game.board().stone_at(1, 2), None);
+    assert_eq!(game.board().stone_at(1, 1), Some('0'));
+}
+
+/// the players take the three kos in turn, each taking
+/// one the opponent did not just take - so the simple ko
+/// rule never applies - until the sixth capture would
+/// bring back the position the game started from
+fn go_round_the_triple_ko(ko: &str) -> Game {
+    let mut game = go_game(TRIPLE_KO, '0', ko);
+    let start = game.board().hash();
+
+    for &(x, y) in [(1, 1), (1, 7), (1, 11), (1, 2), (1, 6)].iter() {
+        assert!(game.board().is_legal(x, y, game.current_player()), "ko={}", ko);
+        play(&mut game, x, y);
+        assert_ne!(game.board().hash(), start);
+    }
+
+    game
+}
+
+#[test]
+fn superko_forbids_going_round_a_triple_ko() {
+    let game = go_round_the_triple_ko("superko");
+
+    assert!(!game.board().is_legal(1, 12, 'X'));
+}
+
+#[test]
+fn simple_ko_allows_going_round_a_triple_ko() {
+    let mut game = go_round_the_triple_ko("simple");
+    let start = go_game(TRIPLE_KO, '0', "simple").board().hash();
+
+    assert!(game.board().is_legal(1, 12, 'X'));
+    play(&mut game, 1, 12);
+
+    assert_eq!(game.board().hash(), start);
+}
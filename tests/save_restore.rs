@@ -0,0 +1,80 @@
+//! Saving a game and loading it again must give back the same
+//! game - the board, the player to move, who plays which pieces
+//! and the turns which can be taken back.
+//!
+//! The game below goes through every kind of entry in the move
+//! history of a save file - stones, a swap, a pass, a
+//! resignation and a player knocked out by a capture.
+
+extern crate nogo;
+
+use nogo::error_handling::validation::validate_new_game_parameters;
+use nogo::game_logic::{Game, Move, PlayerType, Point};
+
+fn new_game() -> Game {
+    let options = ["--players=0:h,X:c,Y:h,Z:c".to_string(), "--swap=on".to_string()];
+    let params = validate_new_game_parameters("h", "c", "6", "6", &options).unwrap();
+    Game::new(params).unwrap()
+}
+
+fn stone(x: i32, y: i32, id: char) -> Move {
+    Move::Stone(Point::new(x, y, id))
+}
+
+fn player_types(game: &Game) -> Vec<Option<PlayerType>> {
+    game.board().player_ids().into_iter().map(|id| game.board().player_type(id)).collect()
+}
+
+fn assert_round_trip(game: &Game) {
+    let restored = Game::restore(&game.save_data()).unwrap();
+
+    assert_eq!(restored.save_data(), game.save_data());
+    assert_eq!(restored.current_player(), game.current_player());
+    assert_eq!(player_types(&restored), player_types(game));
+    assert_eq!(restored.board().move_history(), game.board().move_history());
+    assert_eq!(restored.board().hash(), game.board().hash());
+    assert_eq!(restored.outcome(), game.outcome());
+}
+
+#[test]
+fn every_kind_of_turn_survives_a_round_trip() {
+    let mut game = new_game();
+    assert_round_trip(&game);
+
+    let turns = [stone(2, 2, '0'),
+                 Move::Swap('X'),
+                 stone(0, 1, 'X'),
+                 stone(0, 0, 'Y'),
+                 Move::Pass('Z'),
+                 Move::Resign('0'),
+                 stone(1, 0, 'X'),
+                 stone(5, 5, 'Z')];
+
+    for &mov in turns.iter() {
+        game.play(mov).unwrap();
+        assert_round_trip(&game);
+    }
+
+    // the swap handed the computer the first stone, and
+    // the X stones to the human
+    assert_eq!(game.board().player_type('0'), Some(PlayerType::COMPUTER));
+    assert_eq!(game.board().player_type('X'), Some(PlayerType::HUMAN));
+
+    // X captured the Y stone in the corner, knocking Y out
+    assert!(game.board().is_eliminated('0'));
+    assert!(game.board().is_eliminated('Y'));
+    assert!(game.save_data().last().unwrap().contains("X:1,0 Y:out"));
+}
+
+#[test]
+fn turns_taken_back_after_a_round_trip_restore_the_swap() {
+    let mut game = new_game();
+    game.play(stone(2, 2, '0')).unwrap();
+    game.play(Move::Swap('X')).unwrap();
+
+    let mut restored = Game::restore(&game.save_data()).unwrap();
+    restored.undo();
+
+    assert_eq!(restored.board().player_type('0'), Some(PlayerType::HUMAN));
+    assert_eq!(restored.board().player_type('X'), Some(PlayerType::COMPUTER));
+}
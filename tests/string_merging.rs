@@ -0,0 +1,66 @@
+//! A stone placed next to several strings of its own player
+//! joins them all into one string, with the liberties of all
+//! of them bar the cell the stone was placed on.
+
+extern crate nogo;
+
+use nogo::game_logic::{Game, Move, Point};
+
+/// a game of two human players on the position given by
+/// `rows`, with `mover` to play
+fn game_from(rows: &[&str], mover: char) -> Game {
+    let mut data = vec![format!("{} {} {} players=0:h,X:h", rows.len(), rows[0].len(), mover)];
+    data.extend(rows.iter().map(|row| row.to_string()));
+
+    Game::restore(&data).unwrap()
+}
+
+fn play(game: &mut Game, x: i32, y: i32) {
+    let id = game.current_player();
+    game.play(Move::Stone(Point::new(x, y, id))).unwrap();
+}
+
+#[test]
+fn a_bridging_stone_merges_two_strings() {
+    let mut game = game_from(&[".....", "00.00", ".....", ".....", "....."], '0');
+
+    assert!(game.board().same_group((1, 0), (1, 1)));
+    assert!(!game.board().same_group((1, 1), (1, 3)));
+    assert_eq!(game.board().groups().len(), 2);
+
+    play(&mut game, 1, 2);
+
+    let board = game.board();
+    assert!(board.same_group((1, 0), (1, 4)));
+    assert!(board.same_group((1, 2), (1, 3)));
+    assert_eq!(board.groups().len(), 1);
+    assert_eq!(board.group_members(1, 2).len(), 5);
+    assert_eq!(board.liberties_of_group(1, 0).len(), 10);
+}
+
+#[test]
+fn a_stone_joining_four_strings_merges_them_all() {
+    let mut game = game_from(&[".0...", "0.0..", ".0...", ".....", "....."], '0');
+    assert_eq!(game.board().groups().len(), 4);
+
+    play(&mut game, 1, 1);
+
+    let board = game.board();
+    assert_eq!(board.groups().len(), 1);
+    assert!(board.same_group((0, 1), (1, 0)));
+    assert!(board.same_group((1, 2), (2, 1)));
+    assert_eq!(board.group_members(0, 1).len(), 5);
+    assert_eq!(board.liberties_of_group(1, 1).len(), 6);
+}
+
+#[test]
+fn strings_of_different_players_are_never_merged() {
+    let mut game = game_from(&[".....", "0.X..", ".....", ".....", "....."], 'X');
+
+    play(&mut game, 1, 1);
+
+    let board = game.board();
+    assert!(board.same_group((1, 1), (1, 2)));
+    assert!(!board.same_group((1, 0), (1, 1)));
+    assert_eq!(board.groups().len(), 2);
+}
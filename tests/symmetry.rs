@@ -0,0 +1,88 @@
+//! Positions which differ only by a rotation or a reflection of
+//! the board have the same canonical form, and so the same
+//! canonical key.
+//!
+//! Each test takes a position, builds its image under every
+//! symmetry of the board and checks that all the images agree
+//! on the canonical form.
+
+extern crate nogo;
+
+use nogo::game_logic::{Game, Point};
+use nogo::game_logic::symmetry::Transform;
+
+/// the position given by `rows`, with 0 to move, under the
+/// given extra rules
+fn game_from(rows: &[String], rules: &str) -> Game {
+    let mut data = vec![format!("{} {} 0 players=0:h,X:h {}", rows.len(), rows[0].len(), rules)];
+    data.extend(rows.iter().cloned());
+
+    Game::restore(&data).unwrap()
+}
+
+/// the rows of the position once the board is transformed
+fn transform_rows(rows: &[&str], t: Transform) -> Vec<String> {
+    let limits = (rows.len() as i32, rows[0].len() as i32);
+    let (h, w) = t.limits(limits);
+    let mut image = vec![vec!['.'; w as usize]; h as usize];
+
+    for (x, row) in rows.iter().enumerate() {
+        for (y, c) in row.chars().enumerate() {
+            let p = t.apply(Point::new(x as i32, y as i32, c), limits);
+            image[p.x() as usize][p.y() as usize] = c;
+        }
+    }
+
+    image.into_iter().map(|row| row.into_iter().collect()).collect()
+}
+
+/// check that the images of the position under every
+/// symmetry of the board share its canonical form, and
+/// return how many symmetries there were
+fn assert_same_canonical_form(rows: &[&str], rules: &str) -> usize {
+    let original: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+    let game = game_from(&original, rules);
+    let expected = game.board().canonical_position();
+    let symmetries = game.board().symmetries();
+
+    for &t in symmetries.iter() {
+        let image = game_from(&transform_rows(rows, t), rules);
+        let canonical = image.board().canonical_position();
+
+        assert_eq!(canonical.key, expected.key, "{:?}", t);
+        assert_eq!(canonical.rows, expected.rows, "{:?}", t);
+    }
+
+    symmetries.len()
+}
+
+#[test]
+fn a_square_board_has_the_same_key_under_all_eight_symmetries() {
+    let rows = ["0X...", ".0...", "..X.#", ".....", "X...."];
+
+    assert_eq!(assert_same_canonical_form(&rows, "variant=go"), 8);
+}
+
+#[test]
+fn a_rectangular_board_has_the_same_key_under_its_four_symmetries() {
+    let rows = ["0X....", ".0..X.", "....#.", "X....."];
+
+    assert_eq!(assert_same_canonical_form(&rows, "variant=go"), 4);
+}
+
+#[test]
+fn a_hex_board_has_the_same_key_under_its_symmetries() {
+    let rows = ["0X...", ".0...", "..X..", "....#", "X...."];
+
+    assert!(assert_same_canonical_form(&rows, "variant=go grid=hex") > 1);
+}
+
+#[test]
+fn the_key_tells_apart_positions_not_related_by_a_symmetry() {
+    let rows = |r: &[&str]| r.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+    let corner = game_from(&rows(&["0....", ".....", ".....", ".....", "....."]), "variant=go");
+    let side = game_from(&rows(&[".0...", ".....", ".....", ".....", "....."]), "variant=go");
+
+    assert_ne!(corner.board().canonical_position().key,
+               side.board().canonical_position().key);
+}
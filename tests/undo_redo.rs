@@ -0,0 +1,107 @@
+//! Taking a turn back puts the game back just as it was before
+//! the turn - the strings and their liberties, the position
+//! hash and the player to move - and making it again brings
+//! back the position after it.
+//!
+//! The turns here capture stones in a `go` game, where the
+//! captured stones have to be put back on the board.
+
+extern crate nogo;
+
+use nogo::game_logic::{Game, Move, Point};
+use nogo::game_logic::groups::Group;
+
+/// X to move, with a capture of two 0 stones at (1, 1) and
+/// (1, 2) to be had by playing at (1, 3)
+const CAPTURE: &[&str] = &[".XX..", "X00..", ".XX..", ".....", "....."];
+
+fn go_game(rows: &[&str], mover: char) -> Game {
+    let mut data = vec![format!("{} {} {} players=0:h,X:h variant=go",
+                                rows.len(),
+                                rows[0].len(),
+                                mover)];
+    data.extend(rows.iter().map(|row| row.to_string()));
+
+    Game::restore(&data).unwrap()
+}
+
+fn play(game: &mut Game, x: i32, y: i32) {
+    let id = game.current_player();
+    game.play(Move::Stone(Point::new(x, y, id))).unwrap();
+}
+
+/// everything taking a turn back has to restore
+fn snapshot(game: &Game) -> (Vec<Group>, u64, char, u32) {
+    (game.board().groups(), game.board().hash(), game.current_player(), game.board().captures('X'))
+}
+
+#[test]
+fn undo_puts_captured_stones_back() {
+    let mut game = go_game(CAPTURE, 'X');
+    let before = snapshot(&game);
+
+    play(&mut game, 1, 3);
+    assert_eq!(game.board().stone_at(1, 1), None);
+    assert_eq!(game.board().captures('X'), 2);
+
+    assert_eq!(game.undo(), Some(Move::Stone(Point::new(1, 3, 'X'))));
+    assert_eq!(snapshot(&game), before);
+    assert!(game.board().same_group((1, 1), (1, 2)));
+    assert_eq!(game.board().liberties_of_group(1, 1).len(), 1);
+}
+
+#[test]
+fn redo_makes_the_capture_again() {
+    let mut game = go_game(CAPTURE, 'X');
+    play(&mut game, 1, 3);
+    let after = snapshot(&game);
+
+    game.undo();
+    assert!(game.board().can_redo());
+
+    assert_eq!(game.redo(), Some(Move::Stone(Point::new(1, 3, 'X'))));
+    assert_eq!(snapshot(&game), after);
+    assert!(!game.board().can_redo());
+}
+
+#[test]
+fn every_turn_can_be_taken_back_and_made_again() {
+    let mut game = go_game(CAPTURE, 'X');
+    let mut snapshots = vec![snapshot(&game)];
+
+    // the capture, a move elsewhere, a pass and a stone
+    // played back into the captured cells
+    play(&mut game, 1, 3);
+    snapshots.push(snapshot(&game));
+    play(&mut game, 4, 4);
+    snapshots.push(snapshot(&game));
+    game.play(Move::Pass('X')).unwrap();
+    snapshots.push(snapshot(&game));
+    play(&mut game, 1, 1);
+    snapshots.push(snapshot(&game));
+
+    for expected in snapshots.iter().rev().skip(1) {
+        assert!(game.undo().is_some());
+        assert_eq!(&snapshot(&game), expected);
+    }
+    assert!(game.undo().is_none());
+
+    for expected in snapshots.iter().skip(1) {
+        assert!(game.redo().is_some());
+        assert_eq!(&snapshot(&game), expected);
+    }
+    assert!(game.redo().is_none());
+}
+
+#[test]
+fn a_new_turn_after_undo_cannot_be_redone_over() {
+    let mut game = go_game(CAPTURE, 'X');
+    play(&mut game, 1, 3);
+    game.undo();
+
+    play(&mut game, 4, 4);
+
+    assert!(!game.board().can_redo());
+    assert_eq!(game.board().stone_at(1, 1), Some('0'));
+    assert_eq!(game.current_player(), '0');
+}
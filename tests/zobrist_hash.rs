@@ -0,0 +1,77 @@
+//! The Zobrist hash of a position depends only on the cells of
+//! the board and the seed of the game, so it is the same from
+//! one run to the next, and the same whether the board got to
+//! the position by playing, by taking turns back or by loading
+//! a save file.
+
+extern crate nogo;
+
+use nogo::game_logic::{Game, Move, Point};
+
+/// the position from `rows`, with 0 to move, hashed with
+/// the given seed
+fn game_from(rows: &[&str], seed: u64) -> Game {
+    let mut data = vec![format!("{} {} 0 players=0:h,X:h variant=go hash-seed={}",
+                                rows.len(),
+                                rows[0].len(),
+                                seed)];
+    data.extend(rows.iter().map(|row| row.to_string()));
+
+    Game::restore(&data).unwrap()
+}
+
+const POSITION: &[&str] = &[".....", ".0X..", "..0..", ".X...", "....#"];
+
+const EMPTY_BOARD: &[&str] = &[".....", ".....", ".....", ".....", "....."];
+
+#[test]
+fn the_hash_is_fixed_for_a_given_seed() {
+    let game = game_from(POSITION, 42);
+
+    assert_eq!(game.board().hash_seed(), 42);
+    assert_eq!(game.board().hash(), 0x8ffa_cc90_d8c1_fa31);
+}
+
+#[test]
+fn the_hash_depends_on_the_seed() {
+    assert_ne!(game_from(POSITION, 42).board().hash(),
+               game_from(POSITION, 43).board().hash());
+}
+
+#[test]
+fn an_empty_board_hashes_to_zero() {
+    assert_eq!(game_from(EMPTY_BOARD, 42).board().hash(), 0);
+}
+
+#[test]
+fn the_hash_kept_while_playing_matches_the_position() {
+    let mut game = game_from(EMPTY_BOARD, 42);
+
+    for &(x, y, id) in [(1, 1, '0'), (1, 2, 'X'), (2, 2, '0'), (3, 1, 'X')].iter() {
+        game.play(Move::Stone(Point::new(x, y, id))).unwrap();
+    }
+
+    let rows = [".....", ".0X..", "..0..", ".X...", "....."];
+    assert_eq!(game.board().hash(), game_from(&rows, 42).board().hash());
+}
+
+#[test]
+fn taking_a_turn_back_restores_the_hash() {
+    let mut game = game_from(POSITION, 42);
+    let before = game.board().hash();
+
+    game.play(Move::Stone(Point::new(0, 0, '0'))).unwrap();
+    assert_ne!(game.board().hash(), before);
+
+    game.undo();
+    assert_eq!(game.board().hash(), before);
+}
+
+#[test]
+fn the_seed_survives_a_round_trip() {
+    let game = game_from(POSITION, 42);
+    let restored = Game::restore(&game.save_data()).unwrap();
+
+    assert_eq!(restored.board().hash_seed(), 42);
+    assert_eq!(restored.board().hash(), game.board().hash());
+}